@@ -0,0 +1,270 @@
+use crate::game::Game;
+
+/// Score of a won position. Wins found sooner score higher.
+pub const WIN: i32 = 1_000_000;
+
+/// Boards with more cells than this only consider moves next to existing
+/// pieces, which keeps the search fast on large boards.
+const NEARBY_THRESHOLD: usize = 25;
+
+/// The outcome of searching a position.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Analysis {
+    /// Score for the player to move; positive is good for them.
+    pub score: i32,
+    pub best: Option<(usize, usize)>,
+}
+
+/// Cells the player to move may play, as the cell the piece would land in.
+pub fn legal_moves(game: &Game) -> Vec<(usize, usize)> {
+    if !game.in_progress() {
+        return vec![];
+    }
+    let mut moves = vec![];
+    for col in 0..game.size {
+        if game.gravity {
+            if let Some(row) = (0..game.size)
+                .rev()
+                .find(|row| game.grid.get(col, *row).is_none())
+            {
+                moves.push((col, row));
+            }
+        } else {
+            for row in 0..game.size {
+                if game.grid.get(col, row).is_none() {
+                    moves.push((col, row));
+                }
+            }
+        }
+    }
+    moves
+}
+
+/// Legal moves next to a piece already on the board, ordered by column then
+/// row. Only the cells around the pieces are looked at, so this stays fast on
+/// boards far too large to scan. Any move that completes a line of two or more
+/// is among them.
+pub fn nearby_moves(game: &Game) -> Vec<(usize, usize)> {
+    if !game.in_progress() {
+        return vec![];
+    }
+    let mut moves = vec![];
+    for ((col, row), _) in game.grid.pieces() {
+        for dx in -1..=1i32 {
+            let x = col as i32 + dx;
+            if x < 0 || x as usize >= game.size {
+                continue;
+            }
+            if game.gravity {
+                // Only the cell a piece would land in is a move.
+                moves.extend(game.landing(x as usize, 0));
+                continue;
+            }
+            for dy in -1..=1i32 {
+                let y = row as i32 + dy;
+                if y >= 0
+                    && (y as usize) < game.size
+                    && game.grid.get(x as usize, y as usize).is_none()
+                {
+                    moves.push((x as usize, y as usize));
+                }
+            }
+        }
+    }
+    moves.sort_unstable();
+    moves.dedup();
+    moves.retain(|cell| near_piece(game, *cell));
+    moves
+}
+
+/// Suggest a move for the player to move.
+pub fn suggest(game: &Game) -> Option<(usize, usize)> {
+    analyse(game, default_depth(game)).best
+}
+
+/// Search the position to the given depth in moves.
+pub fn analyse(game: &Game, depth: usize) -> Analysis {
+    let mut game = game.clone();
+    let mut best = None;
+    let mut alpha = -WIN * 2;
+    for (col, row) in candidates(&game) {
+        game.place(col, row);
+        let score = -negamax(&mut game, depth.saturating_sub(1), -WIN * 2, -alpha);
+        game.undo();
+        if best.is_none() || score > alpha {
+            alpha = score;
+            best = Some((col, row));
+        }
+    }
+    Analysis {
+        score: if best.is_some() {
+            alpha
+        } else {
+            terminal_score(&game, depth)
+        },
+        best,
+    }
+}
+
+/// Score every move worth searching to the given depth, best first. Unlike
+/// [`analyse`], which only needs to know which move is best, each score is
+/// exact, so moves can be compared with each other.
+pub fn rank(game: &Game, depth: usize) -> Vec<((usize, usize), i32)> {
+    let mut game = game.clone();
+    let mut ranked = vec![];
+    for (col, row) in candidates(&game) {
+        game.place(col, row);
+        let score = -negamax(&mut game, depth.saturating_sub(1), -WIN * 2, WIN * 2);
+        game.undo();
+        ranked.push(((col, row), score));
+    }
+    ranked.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    ranked
+}
+
+/// A search depth that answers within a moment on the given board: exhaustive
+/// once few cells remain, shallower the larger the board.
+pub fn default_depth(game: &Game) -> usize {
+    let empty = game.size * game.size - game.history.len();
+    let cells = game.size * game.size;
+    if empty <= 10 {
+        empty
+    } else if cells <= NEARBY_THRESHOLD {
+        4
+    } else if cells <= 100 {
+        3
+    } else {
+        2
+    }
+}
+
+/// Static evaluation of the position for the player to move, counting the
+/// lines each player could still complete, weighted by how full they are.
+/// Only lines through a piece count, so just the lines around the pieces are
+/// looked at.
+pub fn evaluate(game: &Game) -> i32 {
+    let mut score = 0;
+    let me = game.turn;
+    let (size, win) = (game.size as i32, game.win as i32);
+    for ((col, row), _) in game.grid.pieces() {
+        for &(dx, dy) in &[(1, 0), (0, 1), (1, 1), (-1, 1)] {
+            // Each line through the piece, by how far along it the piece is.
+            for offset in 0..win {
+                let start = (col as i32 - dx * offset, row as i32 - dy * offset);
+                let end = (start.0 + dx * (win - 1), start.1 + dy * (win - 1));
+                if start.0.min(start.1).min(end.0) < 0 || start.0.max(end.0).max(end.1) >= size {
+                    continue;
+                }
+                let (mut mine, mut theirs, mut first) = (0, 0, None);
+                for ii in 0..win {
+                    let piece = game
+                        .grid
+                        .get((start.0 + dx * ii) as usize, (start.1 + dy * ii) as usize);
+                    if piece.is_some() && first.is_none() {
+                        first = Some(ii);
+                    }
+                    match piece {
+                        Some(player) if player == me => mine += 1,
+                        Some(_) => theirs += 1,
+                        None => {}
+                    }
+                }
+                // Count each line once, from its first piece.
+                if first != Some(offset) {
+                    continue;
+                }
+                if theirs == 0 && mine > 0 {
+                    score += weight(mine);
+                } else if mine == 0 && theirs > 0 {
+                    score -= weight(theirs);
+                }
+            }
+        }
+    }
+    // Keep heuristic scores clear of the scores of won and lost positions.
+    score.clamp(-WIN / 2, WIN / 2)
+}
+
+fn weight(count: u32) -> i32 {
+    10i32.pow(count.min(5))
+}
+
+fn negamax(game: &mut Game, depth: usize, mut alpha: i32, beta: i32) -> i32 {
+    if !game.in_progress() || depth == 0 {
+        return terminal_score(game, depth);
+    }
+    let mut best = -WIN * 2;
+    for (col, row) in candidates(game) {
+        game.place(col, row);
+        let score = -negamax(game, depth - 1, -beta, -alpha);
+        game.undo();
+        best = best.max(score);
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break;
+        }
+    }
+    best
+}
+
+// Scores a position the search stops at. Wins and losses are worth more the
+// more depth was left, so the search prefers quick wins and slow losses.
+fn terminal_score(game: &Game, depth: usize) -> i32 {
+    match game.victor() {
+        Some(player) if player == game.turn => WIN + depth as i32,
+        Some(_) => -WIN - depth as i32,
+        None if game.drawn => 0,
+        None => evaluate(game),
+    }
+}
+
+// Legal moves worth searching, most promising first: moves that win or block
+// a win, then moves closest to the center. Large boards only consider cells
+// next to existing pieces, or the middle of an empty board.
+fn candidates(game: &Game) -> Vec<(usize, usize)> {
+    let moves = if game.size * game.size <= NEARBY_THRESHOLD {
+        legal_moves(game)
+    } else if game.grid.is_empty() {
+        let middle = (game.size - 1) / 2;
+        game.landing(middle, middle).into_iter().collect()
+    } else {
+        nearby_moves(game)
+    };
+    let center = (game.size as i32 - 1) as f32 / 2.0;
+    let mut scored: Vec<_> = moves
+        .into_iter()
+        .map(|(col, row)| {
+            let distance = (col as f32 - center).abs() + (row as f32 - center).abs();
+            let urgency = if game.would_win(col, row, game.turn) {
+                2
+            } else if game.would_win(col, row, game.turn.other()) {
+                1
+            } else {
+                0
+            };
+            ((col, row), urgency, distance)
+        })
+        .collect();
+    scored.sort_by(|a, b| {
+        b.1.cmp(&a.1)
+            .then(a.2.partial_cmp(&b.2).unwrap_or(std::cmp::Ordering::Equal))
+    });
+    scored.into_iter().map(|(cell, _, _)| cell).collect()
+}
+
+fn near_piece(game: &Game, (col, row): (usize, usize)) -> bool {
+    for dx in -1..=1i32 {
+        for dy in -1..=1i32 {
+            let (x, y) = (col as i32 + dx, row as i32 + dy);
+            if x >= 0
+                && y >= 0
+                && (x as usize) < game.size
+                && (y as usize) < game.size
+                && game.grid.get(x as usize, y as usize).is_some()
+            {
+                return true;
+            }
+        }
+    }
+    false
+}
@@ -0,0 +1,38 @@
+use crate::game::{column_name, Game};
+
+/// Render the board as plain text, one line per row, with the columns and rows
+/// labelled in algebraic notation. Empty cells are drawn as `.`.
+///
+/// ```text
+///   a b c
+/// 3 . X .
+/// 2 . O .
+/// 1 . . .
+/// ```
+pub fn render(game: &Game) -> String {
+    let width = game.size.to_string().len();
+    let mut text = String::new();
+    text.push_str(&" ".repeat(width));
+    for col in 0..game.size {
+        text.push(' ');
+        text.push_str(&column_name(col));
+    }
+    text.push('\n');
+    for row in 0..game.size {
+        text.push_str(&format!("{:>width$}", game.row_name(row), width = width));
+        for col in 0..game.size {
+            let symbol = game
+                .grid
+                .get(col, row)
+                .map_or(".", |player| player.symbol());
+            // Column names widen past `z`, so cells are padded to match.
+            text.push_str(&format!(
+                " {:>width$}",
+                symbol,
+                width = column_name(col).len()
+            ));
+        }
+        text.push('\n');
+    }
+    text
+}
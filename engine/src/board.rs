@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use crate::game::Player;
+
+/// Boards with more columns than this only store their occupied cells. Huge
+/// boards are mostly empty, so a dense grid would waste memory and make
+/// every copy of the game slow. The AI only looks around the pieces, so it
+/// stays quick however large the board.
+pub const SPARSE_SIZE: usize = 100;
+
+/// The pieces on a square board, indexed by column then row with row zero at
+/// the top.
+///
+/// Small boards are stored densely and large ones sparsely, which is picked
+/// automatically from the size and makes no difference to callers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Board {
+    size: usize,
+    cells: Cells,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Cells {
+    // Every cell, column by column.
+    Dense(Vec<Option<Player>>),
+    // Only the occupied cells.
+    Sparse(HashMap<(u32, u32), Player>),
+}
+
+impl Board {
+    /// An empty board with `size` columns and rows.
+    pub fn new(size: usize) -> Board {
+        Board {
+            size,
+            cells: if size > SPARSE_SIZE {
+                Cells::Sparse(HashMap::new())
+            } else {
+                Cells::Dense(vec![None; size * size])
+            },
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Whether only the occupied cells are stored.
+    pub fn is_sparse(&self) -> bool {
+        match self.cells {
+            Cells::Dense(_) => false,
+            Cells::Sparse(_) => true,
+        }
+    }
+
+    /// The piece in the given cell, if any. Cells off the board are empty.
+    pub fn get(&self, col: usize, row: usize) -> Option<Player> {
+        if col >= self.size || row >= self.size {
+            return None;
+        }
+        match &self.cells {
+            Cells::Dense(cells) => cells[col * self.size + row],
+            Cells::Sparse(cells) => cells.get(&(col as u32, row as u32)).copied(),
+        }
+    }
+
+    /// Put a piece in the given cell, or clear it with `None`, returning what
+    /// was there before.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is off the board.
+    pub fn set(&mut self, col: usize, row: usize, piece: Option<Player>) -> Option<Player> {
+        assert!(
+            col < self.size && row < self.size,
+            "cell ({}, {}) is off a board of size {}",
+            col,
+            row,
+            self.size
+        );
+        match &mut self.cells {
+            Cells::Dense(cells) => std::mem::replace(&mut cells[col * self.size + row], piece),
+            Cells::Sparse(cells) => {
+                let key = (col as u32, row as u32);
+                match piece {
+                    Some(player) => cells.insert(key, player),
+                    None => cells.remove(&key),
+                }
+            }
+        }
+    }
+
+    /// The occupied cells and their pieces, ordered by column then row.
+    pub fn pieces(&self) -> Vec<((usize, usize), Player)> {
+        match &self.cells {
+            Cells::Dense(cells) => cells
+                .iter()
+                .enumerate()
+                .filter_map(|(ii, cell)| {
+                    cell.map(|player| ((ii / self.size, ii % self.size), player))
+                })
+                .collect(),
+            Cells::Sparse(cells) => {
+                let mut pieces: Vec<((usize, usize), Player)> = cells
+                    .iter()
+                    .map(|(&(col, row), &player)| ((col as usize, row as usize), player))
+                    .collect();
+                pieces.sort_by_key(|(cell, _)| *cell);
+                pieces
+            }
+        }
+    }
+
+    /// How many pieces are on the board.
+    pub fn len(&self) -> usize {
+        match &self.cells {
+            Cells::Dense(cells) => cells.iter().filter(|cell| cell.is_some()).count(),
+            Cells::Sparse(cells) => cells.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
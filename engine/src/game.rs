@@ -0,0 +1,378 @@
+use serde::{Deserialize, Serialize};
+
+use crate::board::Board;
+use crate::observer::{GameObserver, Observers};
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub enum Player {
+    #[default]
+    Naughts,
+    Crosses,
+}
+
+impl Player {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Player::Naughts => "O",
+            Player::Crosses => "X",
+        }
+    }
+
+    pub fn other(&self) -> Player {
+        match self {
+            Player::Naughts => Player::Crosses,
+            Player::Crosses => Player::Naughts,
+        }
+    }
+}
+
+/// Where a round is in its life, which decides what may be done to it.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Phase {
+    /// No move has been played, so the player to move first may still be
+    /// changed.
+    Lobby,
+    /// Moves are being played.
+    Playing,
+    /// The round has been won, drawn or lost on time, and no more moves may be
+    /// played until it is restarted.
+    Finished,
+}
+
+/// The start and end cells of a winning line.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Axis(pub (usize, usize), pub (usize, usize));
+
+/// The rules and the board of a single round.
+#[derive(Clone, Debug)]
+pub struct Game {
+    pub size: usize,
+    pub win: usize,
+    pub gravity: bool,
+    pub grid: Board,
+    /// The player who moves first in this round.
+    pub first: Player,
+    pub turn: Player,
+    pub winner: Option<(Player, Axis)>,
+    /// Whether the round ended with a full board and no winner.
+    pub drawn: bool,
+    /// The player who lost the round by running out of time, if any.
+    pub timed_out: Option<Player>,
+    /// Cells played in this round, in order.
+    pub history: Vec<(usize, usize)>,
+    observers: Observers,
+}
+
+impl Game {
+    pub fn new(size: usize, win: usize, gravity: bool) -> Game {
+        Game {
+            size,
+            win,
+            gravity,
+            grid: Board::new(size),
+            first: Player::Naughts,
+            turn: Player::Naughts,
+            winner: None,
+            drawn: false,
+            timed_out: None,
+            history: vec![],
+            observers: Observers::default(),
+        }
+    }
+
+    /// Have the observer told of moves, takebacks, results and restarts from
+    /// now on.
+    ///
+    /// Observers are not cloned with the game: a clone starts with none, so
+    /// copies searched by the AI do not report their moves. Anything that
+    /// replaces a game with a clone of it must attach its observers again.
+    pub fn observe(&mut self, observer: Box<dyn GameObserver>) {
+        self.observers.0.push(observer);
+    }
+
+    /// Clear the board for a new round with the same rules, the given player
+    /// moving first.
+    pub fn restart(&mut self, first: Player) {
+        let observers = std::mem::take(&mut self.observers);
+        *self = Game::new(self.size, self.win, self.gravity);
+        self.observers = observers;
+        self.set_first(first);
+        self.notify(|observer, game| observer.on_restart(game));
+    }
+
+    /// Have the given player move first. Has no effect outside the lobby.
+    pub fn set_first(&mut self, player: Player) {
+        if self.phase() == Phase::Lobby {
+            self.first = player;
+            self.turn = player;
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        if self.winner.is_some() || self.drawn || self.timed_out.is_some() {
+            Phase::Finished
+        } else if self.history.is_empty() {
+            Phase::Lobby
+        } else {
+            Phase::Playing
+        }
+    }
+
+    /// Whether moves may still be played, which is in the lobby or while
+    /// playing.
+    pub fn in_progress(&self) -> bool {
+        self.phase() != Phase::Finished
+    }
+
+    /// The player who won the round, whether by aligning pieces or on time.
+    pub fn victor(&self) -> Option<Player> {
+        self.winner
+            .map(|(winner, _)| winner)
+            .or_else(|| self.timed_out.map(|player| player.other()))
+    }
+
+    /// End the round with the given player losing on time.
+    pub fn time_out(&mut self, player: Player) {
+        if self.in_progress() {
+            self.timed_out = Some(player);
+            self.notify(|observer, game| observer.on_game_over(game));
+        }
+    }
+
+    /// Place a piece for the current player, ending the round if the move
+    /// wins or fills the board.
+    ///
+    /// When gravity is on the piece falls to the lowest free cell of the
+    /// column and `row` is ignored. Returns the cell the piece landed in, or
+    /// `None` if the move is not allowed.
+    pub fn place(&mut self, col: usize, row: usize) -> Option<(usize, usize)> {
+        let (col, row) = self.landing(col, row)?;
+        self.grid.set(col, row, Some(self.turn));
+        self.history.push((col, row));
+        for (forward, backward) in &[
+            ((1, 0), (-1, 0)),
+            ((0, 1), (0, -1)),
+            ((1, 1), (-1, -1)),
+            ((-1, 1), (1, -1)),
+        ] {
+            let forward_count =
+                self.check_direction(col as i32, row as i32, forward.0, forward.1, self.turn);
+            let backward_count =
+                self.check_direction(col as i32, row as i32, backward.0, backward.1, self.turn);
+            let count = forward_count + backward_count + 1;
+            if count >= self.win {
+                self.winner = Some((
+                    self.turn,
+                    // Calculate the coordinates of the start cell and the end cell.
+                    Axis(
+                        (
+                            (col as i32 + forward.0 * forward_count as i32).max(0) as usize,
+                            (row as i32 + forward.1 * forward_count as i32).max(0) as usize,
+                        ),
+                        (
+                            (col as i32 + backward.0 * backward_count as i32).max(0) as usize,
+                            (row as i32 + backward.1 * backward_count as i32).max(0) as usize,
+                        ),
+                    ),
+                ));
+                break;
+            }
+        }
+        if self.winner.is_none() && self.history.len() == self.size * self.size {
+            self.drawn = true;
+        }
+        let player = self.turn;
+        self.turn = self.turn.other();
+        self.notify(|observer, game| observer.on_move(game, player, (col, row)));
+        if !self.in_progress() {
+            self.notify(|observer, game| observer.on_game_over(game));
+        }
+        Some((col, row))
+    }
+
+    /// The cell a piece played at the given cell would land in, or `None` if
+    /// the move is not allowed.
+    pub fn landing(&self, col: usize, row: usize) -> Option<(usize, usize)> {
+        if !self.in_progress() || col >= self.size || row >= self.size {
+            return None;
+        }
+        if self.gravity {
+            // If gravity is on, we place in the first open cell starting from
+            // the last row.
+            // If the column is completely full, then the click is a non-move.
+            let row = (0..self.size)
+                .rev()
+                .find(|ii| self.grid.get(col, *ii).is_none())?;
+            Some((col, row))
+        } else if self.grid.get(col, row).is_some() {
+            None
+        } else {
+            Some((col, row))
+        }
+    }
+
+    /// Name of a cell in algebraic notation: a letter for the column, counting
+    /// from the left, and a number for the row, counting from the bottom, such
+    /// as `b3`. Columns past `z` continue `aa`, `ab` and so on.
+    pub fn cell_name(&self, (col, row): (usize, usize)) -> String {
+        format!("{}{}", column_name(col), self.row_name(row))
+    }
+
+    /// Number of the given row in algebraic notation, counting from one at
+    /// the bottom of the board.
+    pub fn row_name(&self, row: usize) -> usize {
+        self.size - row
+    }
+
+    /// The cell named in algebraic notation, such as `b3`, or `None` if the
+    /// name is malformed or off the board. When gravity is on the row can be
+    /// left out, since pieces fall to the bottom of the column regardless.
+    pub fn parse_cell(&self, name: &str) -> Option<(usize, usize)> {
+        let name = name.trim().to_lowercase();
+        let split = name
+            .find(|c: char| !c.is_ascii_lowercase())
+            .unwrap_or(name.len());
+        let (letters, digits) = name.split_at(split);
+        if letters.is_empty() {
+            return None;
+        }
+        let col = letters.bytes().try_fold(0usize, |col, c| {
+            col.checked_mul(26)?.checked_add(usize::from(c - b'a') + 1)
+        })? - 1;
+        let row = if digits.is_empty() && self.gravity {
+            0
+        } else {
+            let number = digits.parse::<usize>().ok()?;
+            if number == 0 || number > self.size {
+                return None;
+            }
+            self.size - number
+        };
+        if col >= self.size {
+            return None;
+        }
+        Some((col, row))
+    }
+
+    /// Take back the last move, reopening the round if that move ended it.
+    /// Returns the cell that was cleared.
+    pub fn undo(&mut self) -> Option<(usize, usize)> {
+        let (col, row) = self.history.pop()?;
+        let player = self.grid.set(col, row, None)?;
+        self.turn = player;
+        self.winner = None;
+        self.drawn = false;
+        self.timed_out = None;
+        self.notify(|observer, game| observer.on_undo(game, player, (col, row)));
+        Some((col, row))
+    }
+
+    /// Check that the board, history and result agree with each other and
+    /// with the rules, describing the first inconsistency found. A game only
+    /// ever changed through its methods always passes.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.grid.size() != self.size {
+            return Err(format!("grid is not {0}x{0}", self.size));
+        }
+        let pieces = self.grid.len();
+        if pieces != self.history.len() {
+            return Err(format!(
+                "{} pieces on the board but {} moves played",
+                pieces,
+                self.history.len()
+            ));
+        }
+        let mut player = self.first;
+        for (ii, &(col, row)) in self.history.iter().enumerate() {
+            if col >= self.size || row >= self.size {
+                return Err(format!("move {} is off the board", ii + 1));
+            }
+            if self.grid.get(col, row) != Some(player) {
+                return Err(format!("move {} is not {:?}'s piece", ii + 1, player));
+            }
+            if self.gravity && row + 1 < self.size && self.grid.get(col, row + 1).is_none() {
+                return Err(format!("move {} is floating", ii + 1));
+            }
+            player = player.other();
+        }
+        if self.turn != player {
+            return Err(format!(
+                "{:?} to move after {} moves",
+                self.turn,
+                self.history.len()
+            ));
+        }
+        if let Some((winner, Axis(start, end))) = self.winner {
+            if winner != self.turn.other() {
+                return Err("the winner did not play the last move".to_owned());
+            }
+            if self.grid.get(start.0, start.1) != Some(winner)
+                || self.grid.get(end.0, end.1) != Some(winner)
+            {
+                return Err("the winning line does not end on the winner's pieces".to_owned());
+            }
+        }
+        if self.drawn && (self.winner.is_some() || pieces != self.size * self.size) {
+            return Err("drawn without a full board".to_owned());
+        }
+        if self.timed_out.is_some() && (self.winner.is_some() || self.drawn) {
+            return Err("timed out after the round ended".to_owned());
+        }
+        Ok(())
+    }
+
+    /// Whether the player placing a piece at the given empty cell would
+    /// complete a winning line.
+    pub fn would_win(&self, col: usize, row: usize, player: Player) -> bool {
+        [(1, 0), (0, 1), (1, 1), (-1, 1)].iter().any(|(x, y)| {
+            let forward = self.check_direction(col as i32, row as i32, *x, *y, player);
+            let backward = self.check_direction(col as i32, row as i32, -x, -y, player);
+            forward + backward + 1 >= self.win
+        })
+    }
+
+    // Calls the hook on each observer, which are set aside meanwhile so they
+    // can be handed the game.
+    fn notify<F: FnMut(&mut dyn GameObserver, &Game)>(&mut self, mut hook: F) {
+        if self.observers.0.is_empty() {
+            return;
+        }
+        let mut observers = std::mem::take(&mut self.observers);
+        for observer in &mut observers.0 {
+            hook(observer.as_mut(), self);
+        }
+        self.observers = observers;
+    }
+
+    // Checks for consecutive pieces owned by this player in a given direction,
+    // returning the count of pieces.
+    fn check_direction(&self, col: i32, row: i32, x: i32, y: i32, player: Player) -> usize {
+        let mut count = 0;
+        let mut col = col;
+        let mut row = row;
+        loop {
+            col += x;
+            row += y;
+            if self.size - 1 < col as usize || col < 0 || self.size - 1 < row as usize || row < 0 {
+                return count;
+            }
+            if self.grid.get(col as usize, row as usize) == Some(player) {
+                count += 1;
+            } else {
+                return count;
+            }
+        }
+    }
+}
+
+/// Letters naming the given column in algebraic notation.
+pub fn column_name(col: usize) -> String {
+    let mut name = vec![];
+    let mut col = col + 1;
+    while col > 0 {
+        col -= 1;
+        name.push((b'a' + (col % 26) as u8) as char);
+        col /= 26;
+    }
+    name.iter().rev().collect()
+}
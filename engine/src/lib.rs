@@ -0,0 +1,65 @@
+//! The rules of the game and the formats games are recorded in, free of any
+//! graphics or windowing dependencies so bots, tools and other front ends can
+//! build on them.
+//!
+//! # Game records
+//!
+//! Games are exported as plain text records modelled on chess's PGN, which
+//! are easy to read, write by hand and pass between tools. See
+//! [`record::Record`]. A record is a list of tags followed by the moves:
+//!
+//! ```text
+//! [Naughts "Alice"]
+//! [Crosses "Bob"]
+//! [Date "2019.11.02"]
+//! [Size "3"]
+//! [Win "3"]
+//! [Gravity "false"]
+//! [First "Naughts"]
+//! [Result "1-0"]
+//!
+//! 1. b2 a3 2. c1 a1 3. a2 b3 4. c3 1-0
+//! ```
+//!
+//! - Each tag sits on its own line as `[Name "value"]`. Values cannot contain
+//!   double quotes. Tags the reader does not know are ignored, and missing
+//!   tags take the defaults of a standard 3x3 game.
+//! - `Size`, `Win` and `Gravity` are the rules of the game, and `First` is
+//!   `Naughts` or `Crosses`, whoever moved first. `Date` is `YYYY.MM.DD`, with
+//!   unknown parts written as `?`.
+//! - `Result` is `1-0` when naughts won, `0-1` when crosses won, `1/2-1/2` for
+//!   a draw and `*` for a game still in progress. A `[Termination "time"]` tag
+//!   marks a game lost on time.
+//! - After a blank line come the moves, numbered in pairs starting with the
+//!   first player's, each naming the cell played in algebraic notation: a letter for
+//!   the column from the left and a number for the row from the bottom.
+//!   Columns past `z` continue `aa`, `ab` and so on. With gravity on, the cell
+//!   named is the one the piece landed in. Move numbers and the result at the
+//!   end are optional when reading.
+//!
+//! # Puzzles
+//!
+//! A puzzle is a game record of an unfinished game with a `Puzzle` tag giving
+//! the number of moves the player to move has to force a win in. See
+//! [`puzzle::Puzzle`]. Other readers ignore the tag and see an ordinary
+//! record:
+//!
+//! ```text
+//! [Size "3"]
+//! [Win "3"]
+//! [Puzzle "2"]
+//!
+//! 1. c1 a1 2. a3 b2 *
+//! ```
+
+pub mod ai;
+pub mod ascii;
+pub mod board;
+pub mod game;
+pub mod observer;
+pub mod puzzle;
+pub mod record;
+pub mod replay;
+pub mod review;
+pub mod rng;
+pub mod snapshots;
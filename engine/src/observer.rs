@@ -0,0 +1,44 @@
+use std::fmt;
+
+use crate::game::{Game, Player};
+
+/// Hooks called by a `Game` as the round unfolds, for attaching things such
+/// as persistence, metrics, animation or sound without changing the engine.
+///
+/// Every hook does nothing by default, so observers only implement the ones
+/// they need. Each is given the game as it stands after the event. Observers
+/// must be `Send` so games can still be handed between threads.
+pub trait GameObserver: Send {
+    /// A piece was placed by `player` in `cell`.
+    fn on_move(&mut self, _game: &Game, _player: Player, _cell: (usize, usize)) {}
+
+    /// The round ended, whether won, drawn or lost on time. Called after
+    /// `on_move` when a move ends the round.
+    fn on_game_over(&mut self, _game: &Game) {}
+
+    /// The piece `player` placed in `cell` was taken back, reopening the
+    /// round if that move had ended it.
+    fn on_undo(&mut self, _game: &Game, _player: Player, _cell: (usize, usize)) {}
+
+    /// The board was cleared for a new round.
+    fn on_restart(&mut self, _game: &Game) {}
+}
+
+/// The observers attached to a game.
+///
+/// Observers watch one particular game, so a cloned game, such as a copy
+/// searched by the AI, starts with none.
+#[derive(Default)]
+pub struct Observers(pub(crate) Vec<Box<dyn GameObserver>>);
+
+impl Clone for Observers {
+    fn clone(&self) -> Self {
+        Observers::default()
+    }
+}
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Observers({})", self.0.len())
+    }
+}
@@ -0,0 +1,220 @@
+use std::fmt;
+
+use crate::ai;
+use crate::game::{Game, Player};
+use crate::record::{self, Record};
+use crate::rng::Rng;
+
+/// The puzzles built into the crate, in the order they are set as the daily
+/// puzzle.
+const PACK: &[&str] = &[
+    include_str!("../puzzles/01-corner.txt"),
+    include_str!("../puzzles/02-fork.txt"),
+    include_str!("../puzzles/03-open-three.txt"),
+    include_str!("../puzzles/04-double-threat.txt"),
+    include_str!("../puzzles/05-stack.txt"),
+];
+
+/// How many moves ahead the AI looks when playing itself to find puzzles.
+/// Shallow play leaves more mistakes for the other side to punish.
+const SELF_PLAY_DEPTH: usize = 2;
+
+/// A position in which the player to move can force a win, written in the
+/// puzzle format described in the crate documentation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Puzzle {
+    /// The position to solve, reached by the record's moves.
+    pub record: Record,
+    /// How many moves the player to move has to win in.
+    pub moves: usize,
+}
+
+/// What became of a move played towards solving a puzzle.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Verdict {
+    /// The move won.
+    Solved,
+    /// The win is still forced; the opponent replies in the given cell.
+    Reply((usize, usize)),
+    /// The move let the win slip, or took too long to find it.
+    Failed,
+}
+
+impl Puzzle {
+    /// Read a puzzle, failing with a description of the first problem found.
+    pub fn parse(text: &str) -> Result<Puzzle, String> {
+        let record = Record::parse(text)?;
+        let moves = text
+            .lines()
+            .filter_map(|line| record::tag(line.trim()))
+            .find(|(tag, _)| *tag == "Puzzle")
+            .ok_or_else(|| "missing Puzzle tag".to_owned())?
+            .1
+            .parse::<usize>()
+            .map_err(|_| "Puzzle is not a number".to_owned())?;
+        if moves == 0 {
+            return Err("a puzzle must allow at least one move".to_owned());
+        }
+        if !record.game().in_progress() {
+            return Err("the puzzle's round is already over".to_owned());
+        }
+        Ok(Puzzle { record, moves })
+    }
+
+    /// The puzzles built into the crate.
+    pub fn pack() -> Vec<Puzzle> {
+        PACK.iter()
+            .map(|text| Puzzle::parse(text).expect("parsing built-in puzzle"))
+            .collect()
+    }
+
+    /// The puzzle of the pool set for the given day, counted from any fixed
+    /// date such as the Unix epoch, or `None` if the pool is empty.
+    pub fn daily(pool: &[Puzzle], day: u64) -> Option<&Puzzle> {
+        if pool.is_empty() {
+            return None;
+        }
+        pool.get((day % pool.len() as u64) as usize)
+    }
+
+    /// The position to solve.
+    pub fn game(&self) -> Game {
+        self.record.game()
+    }
+
+    /// The player to move, who is to find the win.
+    pub fn solver(&self) -> Player {
+        self.game().turn
+    }
+
+    /// Judge the solver's latest move in `game`, a position played on from
+    /// the puzzle, replying with the opponent's strongest defence while the
+    /// win is still forced.
+    pub fn judge(&self, game: &Game) -> Verdict {
+        if game.victor() == Some(self.solver()) {
+            return Verdict::Solved;
+        }
+        let played = game
+            .history
+            .len()
+            .saturating_sub(self.record.replay.moves.len());
+        let left = self.moves.saturating_sub(played.div_ceil(2));
+        if !game.in_progress() || left == 0 {
+            return Verdict::Failed;
+        }
+        // Every defence loses within the solver's remaining moves.
+        let analysis = ai::analyse(game, left * 2);
+        match analysis.best {
+            Some(cell) if analysis.score <= -ai::WIN => Verdict::Reply(cell),
+            _ => Verdict::Failed,
+        }
+    }
+}
+
+impl fmt::Display for Puzzle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "[Puzzle \"{}\"]", self.moves)?;
+        write!(f, "{}", self.record)
+    }
+}
+
+/// Whether the player to move can force a win within the given number of
+/// their own moves.
+pub fn wins_within(game: &Game, moves: usize) -> bool {
+    moves > 0 && ai::analyse(game, moves * 2 - 1).score >= ai::WIN
+}
+
+/// The moves that force a win within the given number of the player to
+/// move's own moves.
+pub fn winning_moves(game: &Game, moves: usize) -> Vec<(usize, usize)> {
+    let solver = game.turn;
+    ai::legal_moves(game)
+        .into_iter()
+        .filter(|&(col, row)| {
+            let mut game = game.clone();
+            game.place(col, row);
+            game.victor() == Some(solver)
+                || (game.in_progress()
+                    && moves > 1
+                    && ai::analyse(&game, (moves - 1) * 2).score <= -ai::WIN)
+        })
+        .collect()
+}
+
+/// Finds puzzles by having the AI play itself, with some random moves mixed
+/// in for variety, and keeping positions where exactly one move forces a win
+/// in the given number of moves and no quicker win exists.
+///
+/// Each puzzle comes from a different game. The generator gives up, ending
+/// the iteration, after playing the given number of games in a row without
+/// finding one.
+#[derive(Clone, Debug)]
+pub struct Generator {
+    size: usize,
+    win: usize,
+    gravity: bool,
+    moves: usize,
+    patience: usize,
+    rng: Rng,
+}
+
+impl Generator {
+    pub fn new(size: usize, win: usize, gravity: bool, moves: usize) -> Generator {
+        Generator {
+            size,
+            win,
+            gravity,
+            moves: moves.max(1),
+            patience: 1_000,
+            rng: Rng::new(0x2545_f491_4f6c_dd1d),
+        }
+    }
+
+    /// Play games from a different seed, to find different puzzles.
+    pub fn seed(mut self, seed: u64) -> Generator {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    /// How many games in a row may pass without a puzzle before giving up.
+    pub fn patience(mut self, games: usize) -> Generator {
+        self.patience = games;
+        self
+    }
+
+    // Plays a game until it reaches a puzzle or ends.
+    fn play(&mut self) -> Option<Puzzle> {
+        let mut game = Game::new(self.size, self.win, self.gravity);
+        while game.in_progress() {
+            // Positions this early are rarely interesting.
+            if game.history.len() >= 3
+                && !wins_within(&game, self.moves - 1)
+                && winning_moves(&game, self.moves).len() == 1
+            {
+                let names = ("Naughts".to_owned(), "Crosses".to_owned());
+                let mut record = Record::from_game(&game, &names);
+                record.date = "????.??.??".to_owned();
+                return Some(Puzzle {
+                    record,
+                    moves: self.moves,
+                });
+            }
+            let legal = ai::legal_moves(&game);
+            let (col, row) = if self.rng.below(3) == 0 {
+                legal[self.rng.below(legal.len())]
+            } else {
+                ai::analyse(&game, SELF_PLAY_DEPTH).best.unwrap_or(legal[0])
+            };
+            game.place(col, row);
+        }
+        None
+    }
+}
+
+impl Iterator for Generator {
+    type Item = Puzzle;
+
+    fn next(&mut self) -> Option<Puzzle> {
+        (0..self.patience).find_map(|_| self.play())
+    }
+}
@@ -0,0 +1,199 @@
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::game::{Game, Player};
+use crate::replay::Replay;
+
+/// Largest board a record may describe. Huge boards are stored sparsely, but
+/// searching and drawing them still visits every cell, so larger sizes are
+/// refused to keep a corrupt record from hanging whatever reads it.
+pub const MAX_SIZE: usize = 1_000;
+
+/// A finished or unfinished game along with who played it and when, written
+/// in the game record format described in the crate documentation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    /// Names of the naughts and crosses players.
+    pub names: (String, String),
+    /// Date the game was played, as `YYYY.MM.DD`.
+    pub date: String,
+    pub replay: Replay,
+    /// The player who lost on time, if the game ended that way.
+    pub timed_out: Option<Player>,
+}
+
+impl Record {
+    /// Record the game as played today.
+    pub fn from_game(game: &Game, names: &(String, String)) -> Record {
+        Record {
+            names: names.clone(),
+            date: today(),
+            replay: Replay::from_game(game),
+            timed_out: game.timed_out,
+        }
+    }
+
+    /// The position at the end of the record.
+    pub fn game(&self) -> Game {
+        let mut game = self.replay.end();
+        if let Some(player) = self.timed_out {
+            game.time_out(player);
+        }
+        game
+    }
+
+    /// Read a record, failing with a description of the first problem found.
+    pub fn parse(text: &str) -> Result<Record, String> {
+        let mut record = Record {
+            names: ("Naughts".to_owned(), "Crosses".to_owned()),
+            date: "????.??.??".to_owned(),
+            replay: Replay {
+                size: 3,
+                win: 3,
+                gravity: false,
+                first: Player::Naughts,
+                moves: vec![],
+            },
+            timed_out: None,
+        };
+        let mut result = None;
+        let mut timed_out = false;
+        let mut movetext = String::new();
+        for line in text.lines().map(str::trim) {
+            if !line.starts_with('[') {
+                movetext.push_str(line);
+                movetext.push(' ');
+                continue;
+            }
+            let (tag, value) = tag(line).ok_or_else(|| format!("malformed tag {:?}", line))?;
+            let number = || {
+                value
+                    .parse::<usize>()
+                    .map_err(|_| format!("{} is not a number: {:?}", tag, value))
+            };
+            match tag {
+                "Naughts" => record.names.0 = value.to_owned(),
+                "Crosses" => record.names.1 = value.to_owned(),
+                "Date" => record.date = value.to_owned(),
+                "Size" => record.replay.size = number()?,
+                "Win" => record.replay.win = number()?,
+                "Gravity" => record.replay.gravity = value == "true",
+                "First" if value == "Crosses" => record.replay.first = Player::Crosses,
+                "First" => record.replay.first = Player::Naughts,
+                "Result" => result = Some(value.to_owned()),
+                "Termination" => timed_out = value == "time",
+                // Unknown tags are kept by other tools but mean nothing here.
+                _ => {}
+            }
+        }
+        if record.replay.size == 0 || record.replay.win == 0 {
+            return Err("the board must have a size and a win length".to_owned());
+        }
+        if record.replay.size > MAX_SIZE {
+            return Err(format!("boards larger than {} are not supported", MAX_SIZE));
+        }
+        let mut game = record.replay.start();
+        for token in movetext.split_whitespace() {
+            if token.ends_with('.') || is_result(token) {
+                continue;
+            }
+            let named = game.parse_cell(token);
+            let cell = named
+                .and_then(|(col, row)| game.place(col, row))
+                .ok_or_else(|| format!("illegal move {:?}", token))?;
+            // Pieces fall in gravity games, so a row may be left out, but one
+            // that is given must be where the piece lands.
+            if token.ends_with(|c: char| c.is_ascii_digit()) && named != Some(cell) {
+                return Err(format!(
+                    "move {:?} lands on {}",
+                    token,
+                    game.cell_name(cell)
+                ));
+            }
+            record.replay.moves.push(cell);
+        }
+        if timed_out && game.in_progress() {
+            record.timed_out = match result.as_deref() {
+                Some("1-0") => Some(Player::Crosses),
+                Some("0-1") => Some(Player::Naughts),
+                _ => None,
+            };
+        }
+        Ok(record)
+    }
+
+    // The result in the usual notation, with a naughts win as `1-0` whoever
+    // moved first.
+    fn result(&self) -> &'static str {
+        let game = self.game();
+        match game.victor() {
+            Some(Player::Naughts) => "1-0",
+            Some(Player::Crosses) => "0-1",
+            None if game.drawn => "1/2-1/2",
+            None => "*",
+        }
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "[Naughts \"{}\"]", unquoted(&self.names.0))?;
+        writeln!(f, "[Crosses \"{}\"]", unquoted(&self.names.1))?;
+        writeln!(f, "[Date \"{}\"]", unquoted(&self.date))?;
+        writeln!(f, "[Size \"{}\"]", self.replay.size)?;
+        writeln!(f, "[Win \"{}\"]", self.replay.win)?;
+        writeln!(f, "[Gravity \"{}\"]", self.replay.gravity)?;
+        writeln!(f, "[First \"{:?}\"]", self.replay.first)?;
+        writeln!(f, "[Result \"{}\"]", self.result())?;
+        if self.timed_out.is_some() {
+            writeln!(f, "[Termination \"time\"]")?;
+        }
+        writeln!(f)?;
+        let game = self.replay.start();
+        for (ii, pair) in self.replay.moves.chunks(2).enumerate() {
+            write!(f, "{}.", ii + 1)?;
+            for cell in pair {
+                write!(f, " {}", game.cell_name(*cell))?;
+            }
+            write!(f, " ")?;
+        }
+        writeln!(f, "{}", self.result())
+    }
+}
+
+// Splits a tag line such as `[Size "3"]` into its name and value.
+pub(crate) fn tag(line: &str) -> Option<(&str, &str)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (name, value) = inner.split_at(inner.find(' ')?);
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((name, value))
+}
+
+// Tag values cannot contain double quotes, so any are swapped for single ones.
+fn unquoted(value: &str) -> String {
+    value.replace('"', "'")
+}
+
+fn is_result(token: &str) -> bool {
+    ["1-0", "0-1", "1/2-1/2", "*"].contains(&token)
+}
+
+// Today's date in UTC as `YYYY.MM.DD`.
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() / 86_400)
+        .unwrap_or(0) as i64;
+    // Converts days since the epoch to a civil date, after Howard Hinnant's
+    // `civil_from_days`.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::{Game, Player};
+
+/// The rules of a round and the moves played in it, from which any position
+/// of the round can be reconstructed.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Replay {
+    pub size: usize,
+    pub win: usize,
+    pub gravity: bool,
+    /// The player who moved first. Replays saved before the starting player
+    /// alternated leave this out, and naughts moved first.
+    #[serde(default)]
+    pub first: Player,
+    /// Cells played, in order.
+    pub moves: Vec<(usize, usize)>,
+}
+
+impl Replay {
+    pub fn from_game(game: &Game) -> Replay {
+        Replay {
+            size: game.size,
+            win: game.win,
+            gravity: game.gravity,
+            first: game.first,
+            moves: game.history.clone(),
+        }
+    }
+
+    /// The position after every move.
+    pub fn end(&self) -> Game {
+        self.position(self.moves.len())
+    }
+
+    /// An empty board with the rules of the replayed round.
+    pub fn start(&self) -> Game {
+        let mut game = Game::new(self.size, self.win, self.gravity);
+        game.set_first(self.first);
+        game
+    }
+
+    /// The position after the first `count` moves.
+    pub fn position(&self, count: usize) -> Game {
+        let mut game = self.start();
+        for (col, row) in self.moves.iter().take(count) {
+            game.place(*col, *row);
+        }
+        game
+    }
+}
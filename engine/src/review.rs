@@ -0,0 +1,191 @@
+use std::fmt;
+
+use crate::ai;
+use crate::game::{Game, Player};
+use crate::replay::Replay;
+
+/// How far short of the best move a move may fall, for the player who made
+/// it, before it counts as an evaluation swing. Heuristic scores grow tenfold
+/// with each piece in an open line, so this is about the worth of a line one
+/// piece further along.
+const SWING: i32 = 1_000;
+
+/// Kinds of mistake a review points out, worst first.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Mistake {
+    /// A win was forced and the move let it slip.
+    MissedWin,
+    /// The opponent threatened to win next move and the move did not stop
+    /// them, though it could have.
+    MissedBlock,
+    /// The move was much worse than the best one, such as walking into a
+    /// forced loss.
+    Swing,
+}
+
+impl Mistake {
+    pub fn name(self) -> &'static str {
+        match self {
+            Mistake::MissedWin => "missed win",
+            Mistake::MissedBlock => "missed block",
+            Mistake::Swing => "evaluation swing",
+        }
+    }
+
+    /// Annotation written after the move, as in chess notation.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Mistake::MissedWin | Mistake::MissedBlock => "??",
+            Mistake::Swing => "?",
+        }
+    }
+}
+
+/// What the search made of one move of a reviewed game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Note {
+    pub player: Player,
+    pub cell: (usize, usize),
+    /// Score for naughts before the move, had the best move been played.
+    pub before: i32,
+    /// Score for naughts after the move.
+    pub after: i32,
+    /// The move the search preferred, if any.
+    pub best: Option<(usize, usize)>,
+    pub mistake: Option<Mistake>,
+}
+
+/// A game with every move searched, pointing out missed wins, missed blocks
+/// and evaluation swings, written as a plain text report.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Review {
+    pub replay: Replay,
+    /// One note for each move of the replay, in order.
+    pub notes: Vec<Note>,
+}
+
+impl Review {
+    /// Review the replay, searching each position as deep as the AI would
+    /// when playing it.
+    pub fn new(replay: &Replay) -> Review {
+        let mut game = replay.start();
+        let mut notes = vec![];
+        for &(col, row) in &replay.moves {
+            let before = game.clone();
+            game.place(col, row);
+            notes.push(note(&before, &game, (col, row)));
+        }
+        Review {
+            replay: replay.clone(),
+            notes,
+        }
+    }
+
+    /// How many moves were the given kind of mistake.
+    pub fn count(&self, mistake: Mistake) -> usize {
+        self.notes
+            .iter()
+            .filter(|note| note.mistake == Some(mistake))
+            .count()
+    }
+
+    /// One line summary suitable for display.
+    pub fn summary(&self) -> String {
+        format!(
+            "missed wins {}, missed blocks {}, evaluation swings {}",
+            self.count(Mistake::MissedWin),
+            self.count(Mistake::MissedBlock),
+            self.count(Mistake::Swing)
+        )
+    }
+
+    /// The move at the given index along with what was wrong with it, if
+    /// anything, such as `X a3?? missed block, b1 was best (+0)`.
+    pub fn comment(&self, index: usize) -> Option<String> {
+        let note = self.notes.get(index)?;
+        let game = self.replay.start();
+        let mut comment = format!("{} {}", note.player.symbol(), game.cell_name(note.cell));
+        if let Some(mistake) = note.mistake {
+            comment.push_str(&format!("{} {}", mistake.symbol(), mistake.name()));
+            if let Some(best) = note.best {
+                comment.push_str(&format!(
+                    ", {} was best ({})",
+                    game.cell_name(best),
+                    format_score(note.before)
+                ));
+            }
+        }
+        Some(comment)
+    }
+}
+
+impl fmt::Display for Review {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Review: {}", self.summary())?;
+        writeln!(f)?;
+        for (ii, note) in self.notes.iter().enumerate() {
+            let comment = self.comment(ii).unwrap_or_default();
+            writeln!(
+                f,
+                "{:>3}. {:<9} {}",
+                ii + 1,
+                format_score(note.after),
+                comment
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A score for naughts as shown to players: who wins once the search has
+/// seen a forced win, otherwise the heuristic score.
+pub fn format_score(score: i32) -> String {
+    if score >= ai::WIN {
+        "O wins".to_owned()
+    } else if score <= -ai::WIN {
+        "X wins".to_owned()
+    } else {
+        format!("{:+}", score)
+    }
+}
+
+// Searches the positions either side of a move and judges it against the
+// best move found.
+fn note(before: &Game, after: &Game, cell: (usize, usize)) -> Note {
+    let player = before.turn;
+    let sign = if player == Player::Naughts { 1 } else { -1 };
+    // At least two moves deep, so the move played and the best move are
+    // scored alike.
+    let depth = ai::default_depth(before).max(2);
+    let best = ai::analyse(before, depth);
+    // Scores for the player who moved.
+    let played = -ai::analyse(after, depth - 1).score;
+    let mistake = if best.score >= ai::WIN && played < ai::WIN {
+        Some(Mistake::MissedWin)
+    } else if best.score > -ai::WIN
+        && threatens(before, player.other())
+        && threatens(after, player.other())
+    {
+        Some(Mistake::MissedBlock)
+    } else if (best.score > -ai::WIN && played <= -ai::WIN) || best.score - played >= SWING {
+        Some(Mistake::Swing)
+    } else {
+        None
+    };
+    Note {
+        player,
+        cell,
+        before: best.score * sign,
+        after: played * sign,
+        best: best.best,
+        mistake,
+    }
+}
+
+// Whether the player could win by playing in one of the cells open to the
+// player to move. Winning cells are always next to a piece.
+fn threatens(game: &Game, player: Player) -> bool {
+    ai::nearby_moves(game)
+        .into_iter()
+        .any(|(col, row)| game.would_win(col, row, player))
+}
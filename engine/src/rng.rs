@@ -0,0 +1,24 @@
+/// A seeded pseudo-random number generator, for variety in games, puzzles
+/// and benchmarks that can be repeated from the seed.
+///
+/// It is a linear congruential generator: fast and plenty to vary play, but
+/// predictable, so not for anything that must be hard to guess.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    /// A pseudo-random number below `bound`, or zero if `bound` is zero.
+    pub fn below(&mut self, bound: usize) -> usize {
+        self.state = self
+            .state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (self.state >> 33) as usize % bound.max(1)
+    }
+}
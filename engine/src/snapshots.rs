@@ -0,0 +1,90 @@
+use std::collections::VecDeque;
+
+use crate::game::Game;
+
+/// The last few states of a game, oldest first, for stepping back through how
+/// a position arose and playing on from an earlier state.
+///
+/// Unlike a replay, which only holds the moves of one round, snapshots hold
+/// whole states, so they span restarts and any change made to the game.
+#[derive(Clone, Debug)]
+pub struct Snapshots {
+    states: VecDeque<Game>,
+    capacity: usize,
+    /// Index of the state being inspected, or `None` when at the newest.
+    cursor: Option<usize>,
+}
+
+impl Snapshots {
+    /// Keep at most `capacity` states, forgetting the oldest beyond that.
+    pub fn new(capacity: usize) -> Snapshots {
+        Snapshots {
+            states: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+            cursor: None,
+        }
+    }
+
+    /// Record a new state of the game. If an earlier state was being
+    /// inspected, the states after it are dropped and the new state branches
+    /// off from it.
+    pub fn record(&mut self, game: &Game) {
+        if let Some(cursor) = self.cursor.take() {
+            self.states.truncate(cursor + 1);
+        }
+        if self.states.len() == self.capacity {
+            self.states.pop_front();
+        }
+        self.states.push_back(game.clone());
+    }
+
+    /// Step back to the previous state, returning whether there was one.
+    pub fn back(&mut self) -> bool {
+        match self
+            .cursor
+            .unwrap_or_else(|| self.states.len().saturating_sub(1))
+        {
+            0 => false,
+            index => {
+                self.cursor = Some(index - 1);
+                true
+            }
+        }
+    }
+
+    /// Step forward to the next state, returning whether there was one.
+    pub fn forward(&mut self) -> bool {
+        match self.cursor {
+            Some(index) if index + 2 >= self.states.len() => self.cursor = None,
+            Some(index) => self.cursor = Some(index + 1),
+            None => return false,
+        }
+        true
+    }
+
+    /// Stop inspecting and return to the newest state.
+    pub fn resume(&mut self) {
+        self.cursor = None;
+    }
+
+    /// The state being inspected, if it is not the newest.
+    pub fn inspected(&self) -> Option<&Game> {
+        self.cursor.map(|index| &self.states[index])
+    }
+
+    /// Which state is shown, counting from one for the oldest kept, and how
+    /// many there are.
+    pub fn position(&self) -> (usize, usize) {
+        let len = self.states.len();
+        (self.cursor.map_or(len, |index| index + 1), len)
+    }
+
+    /// Play on from the state being inspected: the states after it are
+    /// dropped and a copy of it is returned. Returns `None` when at the
+    /// newest state already.
+    pub fn branch(&mut self) -> Option<Game> {
+        let cursor = self.cursor.take()?;
+        self.states.truncate(cursor + 1);
+        self.states.back().cloned()
+    }
+}
@@ -0,0 +1,39 @@
+//! Checks the search only looks around the pieces, so huge boards are no
+//! slower to play on than small ones.
+
+use tickticktoe_engine::ai;
+use tickticktoe_engine::game::Game;
+
+#[test]
+fn nearby_moves_surround_the_pieces() {
+    let mut game = Game::new(500, 5, false);
+    game.place(0, 0);
+    game.place(250, 250);
+    let moves = ai::nearby_moves(&game);
+    assert_eq!(moves.len(), 3 + 8);
+    assert!(moves.contains(&(1, 1)));
+    assert!(moves.contains(&(249, 251)));
+    assert!(!moves.contains(&(250, 250)));
+
+    let mut game = Game::new(500, 5, true);
+    game.place(250, 0);
+    assert_eq!(
+        ai::nearby_moves(&game),
+        vec![(249, 499), (250, 498), (251, 499)]
+    );
+}
+
+#[test]
+fn huge_boards_are_searched_around_the_pieces() {
+    let game = Game::new(500, 5, false);
+    assert_eq!(ai::suggest(&game), Some((249, 249)));
+
+    let mut game = Game::new(500, 5, false);
+    for &(col, row) in &[(100, 100), (400, 400), (101, 100), (400, 401), (102, 100)] {
+        game.place(col, row);
+    }
+    // Crosses must stop the line of three from growing into an open four.
+    let (col, row) = ai::suggest(&game).expect("a move");
+    assert!((99..=103).contains(&col) && row == 100);
+    assert!(ai::evaluate(&game) < 0);
+}
@@ -0,0 +1,41 @@
+//! Checks dense and sparse boards behave the same, as callers are not meant
+//! to be able to tell them apart.
+
+use tickticktoe_engine::board::{Board, SPARSE_SIZE};
+use tickticktoe_engine::game::Player;
+
+#[test]
+fn dense_and_sparse_boards_agree() {
+    for &size in &[3, SPARSE_SIZE, SPARSE_SIZE + 1, 500] {
+        let mut board = Board::new(size);
+        assert_eq!(board.is_sparse(), size > SPARSE_SIZE);
+        assert!(board.is_empty());
+        assert_eq!(board.get(size - 1, size - 1), None);
+        // Cells off the board read as empty rather than panicking.
+        assert_eq!(board.get(size, 0), None);
+
+        assert_eq!(board.set(size - 1, 0, Some(Player::Crosses)), None);
+        assert_eq!(board.set(0, 1, Some(Player::Naughts)), None);
+        assert_eq!(
+            board.set(0, 1, Some(Player::Crosses)),
+            Some(Player::Naughts)
+        );
+        assert_eq!(board.get(0, 1), Some(Player::Crosses));
+        assert_eq!(board.len(), 2);
+        assert_eq!(
+            board.pieces(),
+            vec![((0, 1), Player::Crosses), ((size - 1, 0), Player::Crosses)]
+        );
+
+        assert_eq!(board.set(0, 1, None), Some(Player::Crosses));
+        assert_eq!(board.set(size - 1, 0, None), Some(Player::Crosses));
+        assert!(board.is_empty());
+        assert_eq!(board.pieces(), vec![]);
+    }
+}
+
+#[test]
+#[should_panic(expected = "off a board")]
+fn setting_a_cell_off_a_sparse_board_panics() {
+    Board::new(SPARSE_SIZE + 1).set(SPARSE_SIZE + 1, 0, Some(Player::Naughts));
+}
@@ -0,0 +1,93 @@
+//! Replays each game record in `tests/fixtures` through the engine and
+//! compares the final position with the `.expected` file beside it, so
+//! changes to the rules can't silently change how recorded games end.
+//!
+//! Run with `UPDATE_GOLDEN=1` to write the expected files from the current
+//! engine, after checking that any differences are intended.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tickticktoe_engine::ascii;
+use tickticktoe_engine::game::{Axis, Game};
+use tickticktoe_engine::record::Record;
+
+// The final position and result as plain text.
+fn describe(game: &Game) -> String {
+    let result = match (game.winner, game.timed_out) {
+        (Some((player, Axis(start, end))), _) => format!(
+            "{:?} wins from {} to {}",
+            player,
+            game.cell_name(start),
+            game.cell_name(end)
+        ),
+        (None, Some(player)) => format!("{:?} wins on time", player.other()),
+        (None, None) if game.drawn => "Drawn".to_owned(),
+        (None, None) => format!("In progress, {:?} to move", game.turn),
+    };
+    format!("{}\n{}\n", ascii::render(game), result)
+}
+
+fn fixtures() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut paths: Vec<PathBuf> = fs::read_dir(&dir)
+        .expect("reading fixtures directory")
+        .map(|entry| entry.expect("reading fixture").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn replays_match_golden_positions() {
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let mut failures = vec![];
+    for path in fixtures() {
+        let text = fs::read_to_string(&path).expect("reading fixture");
+        let record =
+            Record::parse(&text).unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+        let game = record.game();
+        if let Err(err) = game.validate() {
+            failures.push(format!("{}: invalid game: {}", path.display(), err));
+            continue;
+        }
+        let actual = describe(&game);
+        let expected_path = path.with_extension("expected");
+        if update {
+            fs::write(&expected_path, &actual).expect("writing expected position");
+            continue;
+        }
+        let expected = fs::read_to_string(&expected_path).unwrap_or_default();
+        if actual != expected {
+            failures.push(format!(
+                "{}:\n--- expected\n{}--- actual\n{}",
+                path.display(),
+                expected,
+                actual
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn records_survive_a_round_trip() {
+    for path in fixtures() {
+        let text = fs::read_to_string(&path).expect("reading fixture");
+        let record = Record::parse(&text).expect("parsing fixture");
+        let reread = Record::parse(&record.to_string()).expect("parsing written record");
+        assert_eq!(reread, record, "{}", path.display());
+    }
+}
+
+#[test]
+fn gravity_moves_must_name_the_row_they_land_on() {
+    let tags = "[Size \"7\"]\n[Win \"4\"]\n[Gravity \"true\"]\n\n";
+    let record = Record::parse(&format!("{}1. d d *", tags)).expect("parsing record");
+    assert_eq!(record.replay.moves, vec![(3, 6), (3, 5)]);
+    assert_eq!(
+        Record::parse(&format!("{}1. d1 d1 *", tags)),
+        Err("move \"d1\" lands on d2".to_owned())
+    );
+}
@@ -0,0 +1,73 @@
+//! Checks observers hear about every change to a game, in order, and are
+//! left behind when the game is cloned.
+
+use std::sync::{Arc, Mutex};
+
+use tickticktoe_engine::game::{Game, Player};
+use tickticktoe_engine::observer::GameObserver;
+
+// Writes each event it is told of to a shared log.
+struct Recorder(Arc<Mutex<Vec<String>>>);
+
+impl GameObserver for Recorder {
+    fn on_move(&mut self, game: &Game, player: Player, cell: (usize, usize)) {
+        let event = format!("{} moved {}", player.symbol(), game.cell_name(cell));
+        self.0.lock().unwrap().push(event);
+    }
+
+    fn on_game_over(&mut self, game: &Game) {
+        let event = format!("game over, {:?} won", game.victor());
+        self.0.lock().unwrap().push(event);
+    }
+
+    fn on_undo(&mut self, game: &Game, player: Player, cell: (usize, usize)) {
+        let event = format!("{} took back {}", player.symbol(), game.cell_name(cell));
+        self.0.lock().unwrap().push(event);
+    }
+
+    fn on_restart(&mut self, _game: &Game) {
+        self.0.lock().unwrap().push("restart".to_owned());
+    }
+}
+
+#[test]
+fn observers_hear_moves_takebacks_and_results() {
+    let log = Arc::new(Mutex::new(vec![]));
+    let mut game = Game::new(3, 3, false);
+    game.observe(Box::new(Recorder(log.clone())));
+    for &(col, row) in &[(0, 2), (1, 2), (0, 1)] {
+        game.place(col, row);
+    }
+    game.undo();
+    for &(col, row) in &[(0, 1), (1, 1), (0, 0)] {
+        game.place(col, row);
+    }
+    game.undo();
+    game.restart(Player::Crosses);
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec![
+            "O moved a1",
+            "X moved b1",
+            "O moved a2",
+            "O took back a2",
+            "O moved a2",
+            "X moved b2",
+            "O moved a3",
+            "game over, Some(Naughts) won",
+            "O took back a3",
+            "restart",
+        ]
+    );
+}
+
+#[test]
+fn clones_leave_observers_behind() {
+    let log = Arc::new(Mutex::new(vec![]));
+    let mut game = Game::new(3, 3, false);
+    game.observe(Box::new(Recorder(log.clone())));
+    let mut copy = game.clone();
+    copy.place(1, 1);
+    copy.undo();
+    assert!(log.lock().unwrap().is_empty());
+}
@@ -0,0 +1,39 @@
+//! Checks the puzzles built into the crate, so a change to the search or a
+//! mistyped puzzle can't ship one that has no solution.
+
+use tickticktoe_engine::puzzle::{winning_moves, wins_within, Generator, Puzzle};
+
+#[test]
+fn built_in_puzzles_are_forced_wins() {
+    for (ii, puzzle) in Puzzle::pack().iter().enumerate() {
+        let game = puzzle.game();
+        assert!(
+            wins_within(&game, puzzle.moves),
+            "puzzle {} has no win in {}",
+            ii + 1,
+            puzzle.moves
+        );
+        assert!(
+            !wins_within(&game, puzzle.moves - 1),
+            "puzzle {} can be won in fewer than {} moves",
+            ii + 1,
+            puzzle.moves
+        );
+        assert_eq!(
+            winning_moves(&game, puzzle.moves).len(),
+            1,
+            "puzzle {} has more than one solution",
+            ii + 1
+        );
+    }
+}
+
+#[test]
+fn generated_puzzles_have_one_solution_and_survive_a_round_trip() {
+    for puzzle in Generator::new(4, 3, false, 2).seed(1).take(3) {
+        let game = puzzle.game();
+        assert!(wins_within(&game, 2) && !wins_within(&game, 1));
+        assert_eq!(winning_moves(&game, 2).len(), 1);
+        assert_eq!(Puzzle::parse(&puzzle.to_string()), Ok(puzzle));
+    }
+}
@@ -0,0 +1,40 @@
+//! Checks the computer's review of a game points out the mistakes made in it
+//! and none in a well played one.
+
+use tickticktoe_engine::ai;
+use tickticktoe_engine::game::Game;
+use tickticktoe_engine::record::Record;
+use tickticktoe_engine::replay::Replay;
+use tickticktoe_engine::review::{Mistake, Review};
+
+fn reviewed(movetext: &str) -> Review {
+    let record = Record::parse(movetext).expect("parsing record");
+    Review::new(&record.replay)
+}
+
+#[test]
+fn perfect_play_has_no_mistakes() {
+    let mut game = Game::new(3, 3, false);
+    while let Some((col, row)) = ai::suggest(&game) {
+        game.place(col, row);
+    }
+    let review = Review::new(&Replay::from_game(&game));
+    assert_eq!(review.notes.len(), game.history.len());
+    assert!(review.notes.iter().all(|note| note.mistake.is_none()));
+}
+
+#[test]
+fn missed_wins_and_blocks_are_pointed_out() {
+    // Naughts could complete the a file but play elsewhere.
+    let review = reviewed("1. a1 b1 2. a2 b2 3. c3");
+    assert_eq!(review.notes[4].mistake, Some(Mistake::MissedWin));
+    assert_eq!(review.notes[4].best, Some((0, 0)));
+    assert_eq!(review.count(Mistake::MissedWin), 1);
+
+    // Crosses leave the diagonal through b2 open.
+    let review = reviewed("1. b2 a1 2. a3 c3");
+    assert_eq!(review.notes[3].mistake, Some(Mistake::MissedBlock));
+    assert!(review
+        .to_string()
+        .contains("X c3?? missed block, c1 was best"));
+}
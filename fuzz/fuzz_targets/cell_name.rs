@@ -0,0 +1,19 @@
+//! Reads arbitrary text as a cell name, checking that any cell found is on the
+//! board and named the same way it was read.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tickticktoe_engine::game::Game;
+
+fuzz_target!(|input: (u16, &str)| {
+    let (size, name) = input;
+    let game = Game::new(usize::from(size % 64) + 1, 3, false);
+    if let Some((col, row)) = game.parse_cell(name) {
+        assert!(col < game.size && row < game.size);
+        assert_eq!(
+            game.parse_cell(&game.cell_name((col, row))),
+            Some((col, row))
+        );
+    }
+});
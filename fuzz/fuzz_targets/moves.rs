@@ -0,0 +1,64 @@
+//! Plays arbitrary sequences of actions, including moves off the board and
+//! into full columns, checking the game stays consistent after each one.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use tickticktoe_engine::ai;
+use tickticktoe_engine::game::{Game, Player};
+use tickticktoe_engine::replay::Replay;
+
+#[derive(Arbitrary, Debug)]
+enum Action {
+    Place(u8, u8),
+    Undo,
+    TimeOut(bool),
+    Restart(bool),
+}
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    size: u8,
+    win: u8,
+    gravity: bool,
+    actions: Vec<Action>,
+}
+
+fn player(naughts: bool) -> Player {
+    if naughts {
+        Player::Naughts
+    } else {
+        Player::Crosses
+    }
+}
+
+fuzz_target!(|input: Input| {
+    // Keep boards small enough that every action stays cheap.
+    let size = usize::from(input.size % 12) + 1;
+    let win = usize::from(input.win % 8);
+    let mut game = Game::new(size, win, input.gravity);
+    for action in input.actions {
+        match action {
+            Action::Place(col, row) => {
+                let (col, row) = (usize::from(col), usize::from(row));
+                let landing = game.landing(col, row);
+                assert_eq!(game.place(col, row), landing);
+            }
+            Action::Undo => {
+                game.undo();
+            }
+            Action::TimeOut(naughts) => game.time_out(player(naughts)),
+            Action::Restart(naughts) => game.restart(player(naughts)),
+        }
+        if let Err(err) = game.validate() {
+            panic!("{}: {:?}", err, game);
+        }
+        for (col, row) in ai::legal_moves(&game) {
+            assert_eq!(game.landing(col, row), Some((col, row)));
+        }
+        if game.timed_out.is_none() {
+            assert_eq!(Replay::from_game(&game).end().grid, game.grid);
+        }
+    }
+});
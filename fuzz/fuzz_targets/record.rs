@@ -0,0 +1,25 @@
+//! Reads arbitrary text as a game record, checking that anything accepted is
+//! a consistent game that survives being written out and read back.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tickticktoe_engine::record::Record;
+
+fuzz_target!(|data: &[u8]| {
+    let text = match std::str::from_utf8(data) {
+        Ok(text) => text,
+        Err(_) => return,
+    };
+    let record = match Record::parse(text) {
+        Ok(record) => record,
+        Err(_) => return,
+    };
+    if let Err(err) = record.game().validate() {
+        panic!("{}: {:?}", err, record);
+    }
+    let written = record.to_string();
+    let read = Record::parse(&written).expect("reading a written record");
+    assert_eq!(read.replay, record.replay);
+    assert_eq!(read.timed_out, record.timed_out);
+});
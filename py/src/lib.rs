@@ -0,0 +1,232 @@
+//! Python bindings for the game engine, so experiments such as self-play and
+//! model training run against exactly the rules the game uses.
+//!
+//! ```python
+//! import tickticktoe
+//!
+//! state = tickticktoe.State(size=3, win=3)
+//! while state.in_progress:
+//!     state = tickticktoe.apply(state, tickticktoe.suggest(state))
+//! print(state)
+//! ```
+//!
+//! Cells are `(col, row)` tuples with row zero at the top, or names in
+//! algebraic notation such as `"b3"`. Players are `"O"` for naughts and `"X"`
+//! for crosses.
+
+// PyO3's macros convert every returned error, which clippy flags on functions
+// that already return `PyResult`.
+#![allow(clippy::useless_conversion)]
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use tickticktoe_engine::ai;
+use tickticktoe_engine::ascii;
+use tickticktoe_engine::game::{Game, Phase, Player};
+
+/// A move as given from Python: a cell name or a `(col, row)` tuple.
+#[derive(FromPyObject)]
+enum Move {
+    Name(String),
+    Cell((usize, usize)),
+}
+
+/// The board and rules of a round.
+#[pyclass(module = "tickticktoe")]
+#[derive(Clone)]
+struct State {
+    game: Game,
+}
+
+#[pymethods]
+impl State {
+    #[new]
+    #[pyo3(signature = (size=3, win=3, gravity=false, first="O"))]
+    fn new(size: usize, win: usize, gravity: bool, first: &str) -> PyResult<State> {
+        if size == 0 || win == 0 {
+            return Err(PyValueError::new_err("size and win must be at least 1"));
+        }
+        let mut game = Game::new(size, win, gravity);
+        game.set_first(player(first)?);
+        Ok(State { game: game })
+    }
+
+    /// Play a move for the player to move, in place, returning the cell the
+    /// piece landed in. Raises `ValueError` if the move is not allowed.
+    fn place(&mut self, cell: Move) -> PyResult<(usize, usize)> {
+        let (col, row) = self.cell(cell)?;
+        self.game
+            .place(col, row)
+            .ok_or_else(|| PyValueError::new_err("move not allowed"))
+    }
+
+    /// Take back the last move, returning the cell it was played in.
+    fn undo(&mut self) -> Option<(usize, usize)> {
+        self.game.undo()
+    }
+
+    /// Name of a cell in algebraic notation.
+    fn cell_name(&self, cell: (usize, usize)) -> String {
+        self.game.cell_name(cell)
+    }
+
+    fn copy(&self) -> State {
+        self.clone()
+    }
+
+    #[getter]
+    fn size(&self) -> usize {
+        self.game.size
+    }
+
+    #[getter]
+    fn win(&self) -> usize {
+        self.game.win
+    }
+
+    #[getter]
+    fn gravity(&self) -> bool {
+        self.game.gravity
+    }
+
+    #[getter]
+    fn turn(&self) -> &'static str {
+        self.game.turn.symbol()
+    }
+
+    /// `"lobby"` before the first move, `"playing"` while moves are being
+    /// played and `"finished"` once the round is over.
+    #[getter]
+    fn phase(&self) -> &'static str {
+        match self.game.phase() {
+            Phase::Lobby => "lobby",
+            Phase::Playing => "playing",
+            Phase::Finished => "finished",
+        }
+    }
+
+    #[getter]
+    fn in_progress(&self) -> bool {
+        self.game.in_progress()
+    }
+
+    #[getter]
+    fn winner(&self) -> Option<&'static str> {
+        self.game.victor().map(|player| player.symbol())
+    }
+
+    #[getter]
+    fn drawn(&self) -> bool {
+        self.game.drawn
+    }
+
+    /// The pieces on the board, indexed by column then row.
+    #[getter]
+    fn grid(&self) -> Vec<Vec<Option<&'static str>>> {
+        (0..self.game.size)
+            .map(|col| {
+                (0..self.game.size)
+                    .map(|row| self.game.grid.get(col, row).map(|p| p.symbol()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Cells played so far, in order.
+    #[getter]
+    fn history(&self) -> Vec<(usize, usize)> {
+        self.game.history.clone()
+    }
+
+    fn __str__(&self) -> String {
+        ascii::render(&self.game)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "State(size={}, win={}, gravity={}, moves={:?})",
+            self.game.size,
+            self.game.win,
+            if self.game.gravity { "True" } else { "False" },
+            self.game
+                .history
+                .iter()
+                .map(|cell| self.game.cell_name(*cell))
+                .collect::<Vec<_>>()
+        )
+    }
+}
+
+impl State {
+    fn cell(&self, cell: Move) -> PyResult<(usize, usize)> {
+        match cell {
+            Move::Name(name) => self
+                .game
+                .parse_cell(&name)
+                .ok_or_else(|| PyValueError::new_err(format!("no cell named {:?}", name))),
+            Move::Cell(cell) => Ok(cell),
+        }
+    }
+}
+
+fn player(symbol: &str) -> PyResult<Player> {
+    match symbol {
+        "O" | "o" => Ok(Player::Naughts),
+        "X" | "x" => Ok(Player::Crosses),
+        _ => Err(PyValueError::new_err(format!(
+            "players are \"O\" or \"X\", not {:?}",
+            symbol
+        ))),
+    }
+}
+
+/// The state after playing the move, leaving `state` untouched. Raises
+/// `ValueError` if the move is not allowed.
+#[pyfunction]
+fn apply(state: &State, cell: Move) -> PyResult<State> {
+    let mut next = state.clone();
+    next.place(cell)?;
+    Ok(next)
+}
+
+/// Cells the player to move may play, as the cell the piece would land in.
+#[pyfunction]
+fn legal_moves(state: &State) -> Vec<(usize, usize)> {
+    ai::legal_moves(&state.game)
+}
+
+/// The move the built-in AI would play, or `None` if the round is over.
+#[pyfunction]
+fn suggest(state: &State) -> Option<(usize, usize)> {
+    ai::suggest(&state.game)
+}
+
+/// Search the position `depth` moves deep, returning the score for the
+/// player to move and the best move found. Defaults to the depth the
+/// built-in AI uses.
+#[pyfunction]
+#[pyo3(signature = (state, depth=None))]
+fn analyse(state: &State, depth: Option<usize>) -> (i32, Option<(usize, usize)>) {
+    let depth = depth.unwrap_or_else(|| ai::default_depth(&state.game));
+    let analysis = ai::analyse(&state.game, depth);
+    (analysis.score, analysis.best)
+}
+
+/// Heuristic score of the position for the player to move, without
+/// searching.
+#[pyfunction]
+fn evaluate(state: &State) -> i32 {
+    ai::evaluate(&state.game)
+}
+
+#[pymodule]
+fn tickticktoe(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<State>()?;
+    module.add_function(wrap_pyfunction!(apply, module)?)?;
+    module.add_function(wrap_pyfunction!(legal_moves, module)?)?;
+    module.add_function(wrap_pyfunction!(suggest, module)?)?;
+    module.add_function(wrap_pyfunction!(analyse, module)?)?;
+    module.add_function(wrap_pyfunction!(evaluate, module)?)?;
+    module.add("WIN", ai::WIN)?;
+    Ok(())
+}
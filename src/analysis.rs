@@ -0,0 +1,253 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use crate::ai;
+use crate::game::{Game, Player};
+use crate::replay::Replay;
+use crate::review::Review;
+
+/// How many of the best moves are reported, each with its continuation.
+pub const LINES: usize = 3;
+
+/// How many moves of each continuation are reported.
+const LINE_LENGTH: usize = 4;
+
+/// How much deeper than the AI normally looks the analysis goes, given time.
+const EXTRA_DEPTH: usize = 2;
+
+/// What the search has found so far about a position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    /// How many moves ahead the search looked.
+    pub depth: usize,
+    /// Score of the position for naughts; positive is good for naughts.
+    pub score: i32,
+    /// The best moves found for the player to move, best first.
+    pub lines: Vec<Line>,
+}
+
+/// One of the best moves found, with the play expected to follow it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line {
+    pub cell: (usize, usize),
+    /// Score after the move for naughts.
+    pub score: i32,
+    pub continuation: Vec<(usize, usize)>,
+}
+
+/// Analyses positions on a background thread, searching deeper and deeper
+/// and reporting after each depth, so the game stays responsive however
+/// long the search takes.
+///
+/// A single worker does the searching. When the position changes it drops
+/// the old one after the depth it is on, so positions passing quickly, such
+/// as while scrubbing through a replay, never pile up searches.
+pub struct Analyst {
+    // The position being analysed, to tell when it changes.
+    position: Option<Replay>,
+    positions: Sender<Game>,
+    // Reports along with the position they are about, as the worker may
+    // still be finishing a position that has since been dropped.
+    reports: Receiver<(Replay, Report)>,
+    latest: Option<Report>,
+}
+
+impl Default for Analyst {
+    fn default() -> Analyst {
+        Analyst::new()
+    }
+}
+
+impl Analyst {
+    /// Start the worker, waiting for a position to analyse.
+    pub fn new() -> Analyst {
+        let (positions, inbox) = mpsc::channel();
+        let (outbox, reports) = mpsc::channel();
+        thread::spawn(move || work(inbox, outbox));
+        Analyst {
+            position: None,
+            positions: positions,
+            reports: reports,
+            latest: None,
+        }
+    }
+
+    /// Analyse the position in place of the one before, unless it is already
+    /// being analysed.
+    pub fn analyse(&mut self, game: &Game) {
+        if self.analyses(game) {
+            return;
+        }
+        self.position = Some(Replay::from_game(game));
+        self.latest = None;
+        // The worker only stops once the analyst is dropped.
+        let _ = self.positions.send(game.clone());
+    }
+
+    /// Whether this is an analysis of the given position.
+    pub fn analyses(&self, game: &Game) -> bool {
+        self.position.as_ref() == Some(&Replay::from_game(game))
+    }
+
+    /// Take in any reports on the position that have arrived, returning
+    /// whether there were any.
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        for (position, report) in self.reports.try_iter() {
+            if Some(&position) == self.position.as_ref() {
+                self.latest = Some(report);
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// The deepest report so far, if any.
+    pub fn latest(&self) -> Option<&Report> {
+        self.latest.as_ref()
+    }
+}
+
+// Analyses each position received deeper and deeper, moving on to the
+// newest position received between depths, until the analyst is dropped.
+fn work(positions: Receiver<Game>, reports: Sender<(Replay, Report)>) {
+    let mut next = positions.recv().ok();
+    while let Some(mut game) = next.take() {
+        let position = Replay::from_game(&game);
+        let deepest = ai::default_depth(&game) + EXTRA_DEPTH;
+        for depth in 1..=deepest {
+            next = positions.try_iter().last();
+            if next.is_some() {
+                break;
+            }
+            let report = report(&mut game, depth);
+            if reports.send((position.clone(), report)).is_err() {
+                return;
+            }
+        }
+        if next.is_none() {
+            next = positions.recv().ok();
+        }
+    }
+}
+
+/// Reviews a game on a background thread, as searching every move can take a
+/// while on large boards.
+pub struct Reviewer {
+    reviews: Receiver<Review>,
+}
+
+impl Reviewer {
+    /// Start reviewing the replay.
+    pub fn new(replay: Replay) -> Reviewer {
+        let (sender, reviews) = mpsc::channel();
+        thread::spawn(move || {
+            // Nobody is listening if the review was abandoned.
+            let _ = sender.send(Review::new(&replay));
+        });
+        Reviewer { reviews: reviews }
+    }
+
+    /// The review, once it is finished.
+    pub fn poll(&self) -> Option<Review> {
+        self.reviews.try_recv().ok()
+    }
+}
+
+/// Finds a move to suggest on a background thread, so a slow search does not
+/// freeze the game.
+pub struct Hinter {
+    // The position searched, to tell when the hint no longer applies.
+    position: Replay,
+    hints: Receiver<Option<(usize, usize)>>,
+}
+
+impl Hinter {
+    /// Start searching the position for a move to suggest.
+    pub fn new(game: &Game) -> Hinter {
+        let (sender, hints) = mpsc::channel();
+        let searched = game.clone();
+        thread::spawn(move || {
+            // Nobody is listening if the hint was abandoned.
+            let _ = sender.send(ai::suggest(&searched));
+        });
+        Hinter {
+            position: Replay::from_game(game),
+            hints: hints,
+        }
+    }
+
+    /// Whether the hint is for the given position.
+    pub fn is_for(&self, game: &Game) -> bool {
+        self.position == Replay::from_game(game)
+    }
+
+    /// The move to suggest once the search is finished, which is `None` if
+    /// there was no move to play.
+    pub fn poll(&self) -> Option<Option<(usize, usize)>> {
+        self.hints.try_recv().ok()
+    }
+}
+
+// Searches the position to the given depth.
+fn report(game: &mut Game, depth: usize) -> Report {
+    let sign = if game.turn == Player::Naughts { 1 } else { -1 };
+    let ranked = ai::rank(game, depth);
+    let score = match ranked.first() {
+        Some((_, score)) => *score,
+        None => ai::analyse(game, 0).score,
+    };
+    let lines = ranked
+        .into_iter()
+        .take(LINES)
+        .map(|(cell, score)| {
+            game.place(cell.0, cell.1);
+            let line = Line {
+                cell: cell,
+                score: score * sign,
+                continuation: continuation(game, depth.saturating_sub(1)),
+            };
+            game.undo();
+            line
+        })
+        .collect();
+    Report {
+        depth: depth,
+        score: score * sign,
+        lines: lines,
+    }
+}
+
+// The moves expected to follow, each side playing the best move found at a
+// shrinking depth.
+fn continuation(game: &mut Game, depth: usize) -> Vec<(usize, usize)> {
+    let mut line = vec![];
+    while line.len() < LINE_LENGTH && depth > line.len() {
+        match ai::analyse(game, depth - line.len()).best {
+            Some(cell) => {
+                game.place(cell.0, cell.1);
+                line.push(cell);
+            }
+            None => break,
+        }
+    }
+    for _ in &line {
+        game.undo();
+    }
+    line
+}
+
+/// How far the evaluation bar is filled for naughts, from zero when crosses
+/// are winning to one when naughts are.
+pub fn bar_fraction(score: i32) -> f32 {
+    if score >= ai::WIN {
+        return 1.0;
+    }
+    if score <= -ai::WIN {
+        return 0.0;
+    }
+    // Heuristic scores grow by a factor of ten for each piece in a line, so
+    // they are squashed to keep the bar readable.
+    let score = score as f32;
+    0.5 + 0.5 * score / (score.abs() + 1_000.0)
+}
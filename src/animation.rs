@@ -0,0 +1,167 @@
+use gif::SetParameter;
+use std::borrow::Cow;
+use std::io::Write;
+use std::time::Duration;
+
+use crate::game::{Axis, Game, Player};
+use crate::replay::Replay;
+use crate::theme::{self, Shape, Theme};
+
+/// Width and height of each frame in pixels.
+pub const FRAME_SIZE: u16 = 480;
+
+/// How many times longer than the other frames the final position is shown
+/// for, so the result can be taken in before the animation loops.
+const FINAL_HOLD: u16 = 3;
+
+// Palette indices. The grid takes the color of the player to move unless the
+// theme gives it one, so it has no index of its own in that case.
+const BACKGROUND: u8 = 0;
+const GRID: u8 = 1;
+const NAUGHTS: u8 = 2;
+const CROSSES: u8 = 3;
+const WIN_LINE: u8 = 4;
+
+/// Write an animated GIF showing each position of the replay in turn,
+/// `delay` apart, looping forever.
+///
+/// Frames are rasterized in software with the theme's colors and sizing, so
+/// no window or GPU is needed.
+pub fn render<W: Write>(
+    out: W,
+    replay: &Replay,
+    theme: &Theme,
+    delay: Duration,
+) -> std::io::Result<()> {
+    let palette: Vec<u8> = [
+        opaque(theme.background),
+        theme.grid.unwrap_or(theme.background),
+        theme.naughts,
+        theme.crosses,
+        theme.win_line,
+    ]
+    .iter()
+    .flat_map(|color| color[..3].iter().map(|c| (c * 255.0).round() as u8))
+    .collect();
+    let mut encoder = gif::Encoder::new(out, FRAME_SIZE, FRAME_SIZE, &palette)?;
+    encoder.set(gif::Repeat::Infinite)?;
+    let centis = (delay.as_millis() / 10).max(1).min(u128::from(u16::MAX)) as u16;
+    for count in 0..=replay.moves.len() {
+        let game = replay.position(count);
+        let frame = gif::Frame {
+            width: FRAME_SIZE,
+            height: FRAME_SIZE,
+            buffer: Cow::Owned(rasterize(&game, theme)),
+            delay: if count == replay.moves.len() {
+                centis.saturating_mul(FINAL_HOLD)
+            } else {
+                centis
+            },
+            ..gif::Frame::default()
+        };
+        encoder.write_frame(&frame)?;
+    }
+    Ok(())
+}
+
+// The board as palette indices, row by row.
+fn rasterize(game: &Game, theme: &Theme) -> Vec<u8> {
+    let side = f32::from(FRAME_SIZE);
+    let cell = side / game.size as f32;
+    let grid = match (theme.grid, game.turn) {
+        (Some(_), _) => GRID,
+        (None, Player::Naughts) => NAUGHTS,
+        (None, Player::Crosses) => CROSSES,
+    };
+    let grid_stroke = theme::stroke(theme.grid_stroke, cell) / 2.0;
+    let piece_stroke = theme::stroke(theme.piece_stroke, cell) / 2.0;
+    let win_stroke = theme::stroke(theme.win_line_stroke, cell) / 2.0;
+    let radius = theme::mark_radius(cell);
+    let win_line = game.winner.map(|(_, Axis(start, end))| {
+        let center =
+            |(col, row): (usize, usize)| [cell * (col as f32 + 0.5), cell * (row as f32 + 0.5)];
+        (center(start), center(end))
+    });
+    let mut pixels = Vec::with_capacity(usize::from(FRAME_SIZE) * usize::from(FRAME_SIZE));
+    for y in 0..FRAME_SIZE {
+        for x in 0..FRAME_SIZE {
+            let p = [f32::from(x) + 0.5, f32::from(y) + 0.5];
+            let col = ((p[0] / cell) as usize).min(game.size - 1);
+            let row = ((p[1] / cell) as usize).min(game.size - 1);
+            let center = [cell * (col as f32 + 0.5), cell * (row as f32 + 0.5)];
+            let index = if win_line.map_or(false, |(a, b)| distance(p, a, b) <= win_stroke) {
+                WIN_LINE
+            } else if game.grid.get(col, row).map_or(false, |player| {
+                on_mark(theme.shape(player), p, center, radius, piece_stroke)
+            }) {
+                match game.grid.get(col, row) {
+                    Some(Player::Naughts) => NAUGHTS,
+                    _ => CROSSES,
+                }
+            } else if on_grid(p[0], cell, side, grid_stroke)
+                || on_grid(p[1], cell, side, grid_stroke)
+            {
+                grid
+            } else {
+                BACKGROUND
+            };
+            pixels.push(index);
+        }
+    }
+    pixels
+}
+
+// Whether the coordinate is within `half` of an inner grid line.
+fn on_grid(coordinate: f32, cell: f32, side: f32, half: f32) -> bool {
+    let nearest = (coordinate / cell).round() * cell;
+    nearest > 0.0 && nearest < side - cell / 2.0 && (coordinate - nearest).abs() <= half
+}
+
+// Whether the point is on the outline of a mark centered on `center`.
+fn on_mark(shape: Shape, p: [f32; 2], center: [f32; 2], radius: f32, half: f32) -> bool {
+    let [x, y] = center;
+    match shape {
+        Shape::Circle => {
+            let (dx, dy) = (p[0] - x, p[1] - y);
+            ((dx * dx + dy * dy).sqrt() - radius).abs() <= half
+        }
+        Shape::Cross => {
+            distance(p, [x - radius, y - radius], [x + radius, y + radius]) <= half
+                || distance(p, [x + radius, y - radius], [x - radius, y + radius]) <= half
+        }
+        Shape::Triangle => {
+            let [a, b, c] = theme::triangle(x, y, radius);
+            distance(p, a, b) <= half || distance(p, b, c) <= half || distance(p, c, a) <= half
+        }
+        Shape::Square => {
+            let side = theme::square_half_side(radius);
+            let edge = (p[0] - x).abs().max((p[1] - y).abs());
+            (edge - side).abs() <= half
+        }
+    }
+}
+
+// Distance from the point to the line segment from `a` to `b`.
+fn distance(p: [f32; 2], a: [f32; 2], b: [f32; 2]) -> f32 {
+    let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+    let length = dx * dx + dy * dy;
+    let t = if length > 0.0 {
+        (((p[0] - a[0]) * dx + (p[1] - a[1]) * dy) / length)
+            .max(0.0)
+            .min(1.0)
+    } else {
+        0.0
+    };
+    let (x, y) = (a[0] + dx * t - p[0], a[1] + dy * t - p[1]);
+    (x * x + y * y).sqrt()
+}
+
+// GIF frames have no alpha, so a transparent background is drawn as black.
+fn opaque(color: [f32; 4]) -> [f32; 4] {
+    [
+        color[0] * color[3],
+        color[1] * color[3],
+        color[2] * color[3],
+        1.0,
+    ]
+}
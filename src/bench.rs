@@ -0,0 +1,114 @@
+use std::hint::black_box;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crate::game::Game;
+use crate::replay::Replay;
+use crate::rng::Rng;
+
+/// Board sizes the engine is timed on. The largest is over
+/// `board::SPARSE_SIZE`, so the sparse board is timed too.
+const SIZES: &[usize] = &[3, 7, 15, 31, 127];
+
+/// How long each operation is repeated for before its time is averaged.
+const SAMPLE_TIME: Duration = Duration::from_millis(200);
+
+/// Time the engine's core operations on boards of several sizes and write a
+/// table of the average time each takes.
+///
+/// Moves are played in a fixed pseudo-random order, so results are comparable
+/// between runs and between versions of the engine.
+pub fn run<W: Write>(mut out: W) -> io::Result<()> {
+    writeln!(
+        out,
+        "{:>5} {:>12} {:>12} {:>12} {:>12}",
+        "size", "place", "win check", "clone", "serialize"
+    )?;
+    for &size in SIZES {
+        let win = size.min(5);
+        let order = shuffled(size);
+        let start = Game::new(size, win, false);
+        let mut middle = start.clone();
+        for (col, row) in order.iter().take(size * size / 2) {
+            middle.place(*col, *row);
+        }
+        // Each move of a round played until it ends.
+        let place = measure(|| {
+            let mut game = start.clone();
+            let moves = order
+                .iter()
+                .take_while(|(col, row)| game.place(*col, *row).is_some())
+                .count();
+            black_box(game);
+            moves
+        });
+        // Whether each player would win at each empty cell of a position.
+        let win_check = measure(|| {
+            let mut checks = 0;
+            for (col, row) in &order {
+                if middle.grid.get(*col, *row).is_none() {
+                    for player in &[middle.turn, middle.turn.other()] {
+                        black_box(middle.would_win(*col, *row, *player));
+                        checks += 1;
+                    }
+                }
+            }
+            checks
+        });
+        let clone = measure(|| {
+            black_box(middle.clone());
+            1
+        });
+        // A round of encoding and decoding a position's replay.
+        let serialize = measure(|| {
+            let encoded = toml::to_string(&Replay::from_game(&middle)).expect("encoding replay");
+            let decoded: Replay = toml::from_str(&encoded).expect("decoding replay");
+            black_box(decoded);
+            1
+        });
+        writeln!(
+            out,
+            "{:>5} {:>12} {:>12} {:>12} {:>12}",
+            size,
+            format_time(place),
+            format_time(win_check),
+            format_time(clone),
+            format_time(serialize)
+        )?;
+    }
+    Ok(())
+}
+
+// Average time of an operation, repeating the closure, which returns how many
+// times it performed the operation, for the sample time.
+fn measure<F: FnMut() -> usize>(mut f: F) -> Duration {
+    let started = Instant::now();
+    let mut count = 0u32;
+    while started.elapsed() < SAMPLE_TIME {
+        count += f() as u32;
+    }
+    started.elapsed() / count
+}
+
+fn format_time(time: Duration) -> String {
+    let nanos = time.as_nanos();
+    if nanos < 10_000 {
+        format!("{} ns", nanos)
+    } else if nanos < 10_000_000 {
+        format!("{} µs", nanos / 1_000)
+    } else {
+        format!("{} ms", nanos / 1_000_000)
+    }
+}
+
+// Every cell of the board in a fixed pseudo-random order.
+fn shuffled(size: usize) -> Vec<(usize, usize)> {
+    let mut cells: Vec<(usize, usize)> = (0..size)
+        .flat_map(|col| (0..size).map(move |row| (col, row)))
+        .collect();
+    let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+    for ii in (1..cells.len()).rev() {
+        cells.swap(ii, rng.below(ii + 1));
+    }
+    cells
+}
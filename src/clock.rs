@@ -0,0 +1,146 @@
+use std::time::{Duration, Instant};
+
+use crate::game::Player;
+
+/// Remaining time below which the clock is shown as running out.
+pub const LOW_TIME: Duration = Duration::from_secs(10);
+
+/// How much time each player has for a round.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct TimeControl {
+    /// Time in each player's bank at the start of the round.
+    pub limit: Duration,
+    /// Time added to a player's bank after each of their moves, as with
+    /// Fischer clocks.
+    pub increment: Duration,
+    /// Time each move may take before the player's bank starts running down.
+    pub delay: Duration,
+}
+
+impl TimeControl {
+    /// Read a time control written as seconds for the round, optionally
+    /// followed by `+` and seconds of increment, such as `60+2`.
+    pub fn parse(text: &str) -> Option<TimeControl> {
+        let seconds = |text: &str| text.trim().parse::<u64>().ok().map(Duration::from_secs);
+        let mut parts = text.splitn(2, '+');
+        Some(TimeControl {
+            limit: seconds(parts.next()?)?,
+            increment: match parts.next() {
+                Some(increment) => seconds(increment)?,
+                None => Duration::from_secs(0),
+            },
+            delay: Duration::from_secs(0),
+        })
+    }
+}
+
+/// A chess clock: each player has a bank of time that runs down while it is
+/// their turn.
+#[derive(Clone, Debug)]
+pub struct Clock {
+    pub control: TimeControl,
+    remaining: (Duration, Duration),
+    // The player whose time is running, and since when.
+    running: Option<(Player, Instant)>,
+    // Both banks as they stood after the last move.
+    settled: (Duration, Duration),
+    // Both banks as they stood before each move, so moves can be taken back.
+    taken: Vec<(Duration, Duration)>,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Clock {
+        Clock {
+            control: control,
+            remaining: (control.limit, control.limit),
+            running: None,
+            settled: (control.limit, control.limit),
+            taken: vec![],
+        }
+    }
+
+    /// Refill both banks and stop the clock.
+    pub fn reset(&mut self) {
+        *self = Clock::new(self.control);
+    }
+
+    pub fn remaining(&self, player: Player) -> Duration {
+        let banked = match player {
+            Player::Naughts => self.remaining.0,
+            Player::Crosses => self.remaining.1,
+        };
+        match self.running {
+            Some((running, since)) if running == player => {
+                let spent = since.elapsed().checked_sub(self.control.delay);
+                banked
+                    .checked_sub(spent.unwrap_or_default())
+                    .unwrap_or_default()
+            }
+            _ => banked,
+        }
+    }
+
+    /// Stop the given player's time after they have moved, adding the
+    /// increment to their bank.
+    pub fn moved(&mut self, player: Player) {
+        self.taken.push(self.settled);
+        if self.running() == Some(player) {
+            self.stop();
+            let bank = match player {
+                Player::Naughts => &mut self.remaining.0,
+                Player::Crosses => &mut self.remaining.1,
+            };
+            *bank += self.control.increment;
+        }
+        self.settled = self.remaining;
+    }
+
+    /// Take back the last move, stopping the clock and restoring both banks
+    /// to how they stood before it, increment and all.
+    pub fn undone(&mut self) {
+        if let Some(banks) = self.taken.pop() {
+            self.running = None;
+            self.remaining = banks;
+            self.settled = banks;
+        }
+    }
+
+    /// Stop the running player's time and start the given player's. Their
+    /// time carries on undisturbed if it is already running.
+    pub fn run(&mut self, player: Player) {
+        if self.running() == Some(player) {
+            return;
+        }
+        self.stop();
+        self.running = Some((player, Instant::now()));
+    }
+
+    pub fn stop(&mut self) {
+        if let Some((player, _)) = self.running {
+            let remaining = self.remaining(player);
+            match player {
+                Player::Naughts => self.remaining.0 = remaining,
+                Player::Crosses => self.remaining.1 = remaining,
+            }
+            self.running = None;
+        }
+    }
+
+    pub fn running(&self) -> Option<Player> {
+        self.running.map(|(player, _)| player)
+    }
+
+    /// The player who has run out of time, if any.
+    pub fn flagged(&self) -> Option<Player> {
+        self.running()
+            .filter(|player| self.remaining(*player) == Duration::from_secs(0))
+    }
+}
+
+/// Format a duration as minutes and seconds, rounding up so that the clock
+/// only reads zero once time has run out.
+pub fn format(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    let seconds = (millis + 999) / 1000;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
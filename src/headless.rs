@@ -0,0 +1,68 @@
+use std::io::{self, BufRead, Write};
+
+use crate::ascii;
+use crate::game::{Game, Player};
+
+/// Play a round without a window, reading moves in algebraic notation from
+/// `input`, one per line, and writing the board after each move to `output`.
+///
+/// Moves that are malformed or not allowed are reported and the same player
+/// is asked again. The round ends when it is won or drawn, when `quit` is
+/// read, or when the input runs out. Lines starting with `#` are ignored, so
+/// scripted games can be annotated.
+pub fn run<R: BufRead, W: Write>(
+    input: R,
+    mut output: W,
+    mut game: Game,
+    names: &(String, String),
+) -> io::Result<()> {
+    let name = |player: Player| match player {
+        Player::Naughts => &names.0,
+        Player::Crosses => &names.1,
+    };
+    writeln!(output, "{}", ascii::render(&game))?;
+    writeln!(output, "{} to play.", name(game.turn))?;
+    output.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.eq_ignore_ascii_case("quit") {
+            break;
+        }
+        let player = game.turn;
+        let placed = game
+            .parse_cell(line)
+            .and_then(|(col, row)| game.place(col, row));
+        let cell = match placed {
+            Some(cell) => cell,
+            None => {
+                writeln!(output, "Illegal move {:?}. {} to play.", line, name(player))?;
+                output.flush()?;
+                continue;
+            }
+        };
+        writeln!(
+            output,
+            "{} plays {}.\n{}",
+            name(player),
+            game.cell_name(cell),
+            ascii::render(&game)
+        )?;
+        match game.victor() {
+            Some(winner) => {
+                writeln!(output, "{} wins.", name(winner))?;
+                break;
+            }
+            None if game.drawn => {
+                writeln!(output, "The round is drawn.")?;
+                break;
+            }
+            None => writeln!(output, "{} to play.", name(game.turn))?,
+        }
+        output.flush()?;
+    }
+    output.flush()
+}
@@ -0,0 +1,327 @@
+use ggez::event::KeyCode;
+use ggez::input::keyboard::KeyMods;
+use serde::{Deserialize, Serialize};
+
+/// Something a key can be bound to.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Command {
+    /// Place a piece at the keyboard cursor.
+    Place,
+    /// Start a new round, or place at the cursor while the round is on.
+    Restart,
+    Undo,
+    ToggleMode,
+    /// Open or close the replay viewer.
+    Viewer,
+    Hint,
+    /// Cancel a pending move, close the viewer or open the menu.
+    Back,
+    ResetView,
+    Coordinates,
+    Save,
+    Load,
+    Screenshot,
+    ExportSvg,
+    /// Export the game as an animated GIF.
+    ExportGif,
+    /// Export the game as a text game record, or as its review while a
+    /// reviewed replay is open in the viewer.
+    ExportRecord,
+    /// Show or hide the evaluation bar and the best continuations.
+    Analysis,
+    /// Have the computer review the finished game or the replay being viewed.
+    Review,
+    Debug,
+    /// Move the cursor, or the selection in the menu.
+    Up,
+    Down,
+    /// Move the cursor, or step through the replay in the viewer.
+    Left,
+    Right,
+    /// Jump to the start of the replay in the viewer.
+    ReplayStart,
+    /// Jump to the end of the replay in the viewer.
+    ReplayEnd,
+    /// Play or pause the replay in the viewer.
+    Autoplay,
+    /// Step to the next mistake of a reviewed replay in the viewer.
+    NextMistake,
+    /// Step back through the debug snapshots.
+    SnapshotBack,
+    /// Step forward through the debug snapshots.
+    SnapshotForward,
+}
+
+/// Keys bound to each command, as written in the settings file.
+///
+/// A key is named by its key code, such as `U`, `0`, `F12` or `Return`,
+/// optionally prefixed by modifiers, such as `Ctrl+Z` or `Ctrl+Shift+S`.
+/// Names are not case sensitive. A command left out of the file keeps its
+/// default keys.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub place: Vec<String>,
+    pub restart: Vec<String>,
+    pub undo: Vec<String>,
+    pub toggle_mode: Vec<String>,
+    pub viewer: Vec<String>,
+    pub hint: Vec<String>,
+    pub back: Vec<String>,
+    pub reset_view: Vec<String>,
+    pub coordinates: Vec<String>,
+    pub save: Vec<String>,
+    pub load: Vec<String>,
+    pub screenshot: Vec<String>,
+    pub export_svg: Vec<String>,
+    pub export_gif: Vec<String>,
+    pub export_record: Vec<String>,
+    pub analysis: Vec<String>,
+    pub review: Vec<String>,
+    pub debug: Vec<String>,
+    pub up: Vec<String>,
+    pub down: Vec<String>,
+    pub left: Vec<String>,
+    pub right: Vec<String>,
+    pub replay_start: Vec<String>,
+    pub replay_end: Vec<String>,
+    pub autoplay: Vec<String>,
+    pub next_mistake: Vec<String>,
+    pub snapshot_back: Vec<String>,
+    pub snapshot_forward: Vec<String>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let keys = |names: &[&str]| names.iter().map(|name| (*name).to_owned()).collect();
+        KeyBindings {
+            place: keys(&["Space"]),
+            restart: keys(&["Return"]),
+            undo: keys(&["U", "Ctrl+Z"]),
+            toggle_mode: keys(&["T"]),
+            viewer: keys(&["V"]),
+            hint: keys(&["H"]),
+            back: keys(&["Escape"]),
+            reset_view: keys(&["0"]),
+            coordinates: keys(&["C"]),
+            save: keys(&["Ctrl+S"]),
+            load: keys(&["Ctrl+O"]),
+            screenshot: keys(&["F12"]),
+            export_svg: keys(&["E"]),
+            export_gif: keys(&["G"]),
+            export_record: keys(&["R"]),
+            analysis: keys(&["A"]),
+            review: keys(&["Shift+A"]),
+            debug: keys(&["F3"]),
+            up: keys(&["Up"]),
+            down: keys(&["Down"]),
+            left: keys(&["Left"]),
+            right: keys(&["Right"]),
+            replay_start: keys(&["Home"]),
+            replay_end: keys(&["End"]),
+            autoplay: keys(&["P"]),
+            next_mistake: keys(&["N"]),
+            snapshot_back: keys(&["PageUp"]),
+            snapshot_forward: keys(&["PageDown"]),
+        }
+    }
+}
+
+impl KeyBindings {
+    fn commands(&self) -> Vec<(Command, &str, &[String])> {
+        vec![
+            (Command::Place, "place", &self.place),
+            (Command::Restart, "restart", &self.restart),
+            (Command::Undo, "undo", &self.undo),
+            (Command::ToggleMode, "toggle_mode", &self.toggle_mode),
+            (Command::Viewer, "viewer", &self.viewer),
+            (Command::Hint, "hint", &self.hint),
+            (Command::Back, "back", &self.back),
+            (Command::ResetView, "reset_view", &self.reset_view),
+            (Command::Coordinates, "coordinates", &self.coordinates),
+            (Command::Save, "save", &self.save),
+            (Command::Load, "load", &self.load),
+            (Command::Screenshot, "screenshot", &self.screenshot),
+            (Command::ExportSvg, "export_svg", &self.export_svg),
+            (Command::ExportGif, "export_gif", &self.export_gif),
+            (Command::ExportRecord, "export_record", &self.export_record),
+            (Command::Analysis, "analysis", &self.analysis),
+            (Command::Review, "review", &self.review),
+            (Command::Debug, "debug", &self.debug),
+            (Command::Up, "up", &self.up),
+            (Command::Down, "down", &self.down),
+            (Command::Left, "left", &self.left),
+            (Command::Right, "right", &self.right),
+            (Command::ReplayStart, "replay_start", &self.replay_start),
+            (Command::ReplayEnd, "replay_end", &self.replay_end),
+            (Command::Autoplay, "autoplay", &self.autoplay),
+            (Command::NextMistake, "next_mistake", &self.next_mistake),
+            (Command::SnapshotBack, "snapshot_back", &self.snapshot_back),
+            (
+                Command::SnapshotForward,
+                "snapshot_forward",
+                &self.snapshot_forward,
+            ),
+        ]
+    }
+}
+
+// A key along with the modifiers that must be held with it.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct Binding {
+    code: KeyCode,
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+}
+
+/// Looks up the command bound to a key press.
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<(Binding, Command)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::new(&KeyBindings::default()).expect("default key bindings are valid")
+    }
+}
+
+impl Keymap {
+    /// Build the keymap, failing with a description of the problem if a key
+    /// name is not recognised or a key is bound to more than one command.
+    pub fn new(keys: &KeyBindings) -> Result<Keymap, String> {
+        let mut bindings: Vec<(Binding, Command, &str)> = vec![];
+        for (command, command_name, names) in keys.commands() {
+            for name in names {
+                let binding = parse(name)
+                    .ok_or_else(|| format!("unknown key {:?} for {}", name, command_name))?;
+                if let Some((_, _, other)) = bindings.iter().find(|(bound, _, _)| *bound == binding)
+                {
+                    return Err(format!(
+                        "{:?} is bound to both {} and {}",
+                        name, other, command_name
+                    ));
+                }
+                bindings.push((binding, command, command_name));
+            }
+        }
+        Ok(Keymap {
+            bindings: bindings
+                .into_iter()
+                .map(|(binding, command, _)| (binding, command))
+                .collect(),
+        })
+    }
+
+    /// The command bound to the key with exactly the given modifiers held.
+    pub fn command(&self, code: KeyCode, mods: KeyMods) -> Option<Command> {
+        let pressed = Binding {
+            code: code,
+            ctrl: mods.contains(KeyMods::CTRL),
+            shift: mods.contains(KeyMods::SHIFT),
+            alt: mods.contains(KeyMods::ALT),
+        };
+        self.bindings
+            .iter()
+            .find(|(binding, _)| *binding == pressed)
+            .map(|(_, command)| *command)
+    }
+}
+
+// Parses a key name such as `Ctrl+Z`.
+fn parse(name: &str) -> Option<Binding> {
+    let name = name.to_lowercase();
+    let mut parts: Vec<&str> = name.split('+').map(str::trim).collect();
+    let key = parts.pop()?;
+    let mut binding = Binding {
+        code: key_code(key)?,
+        ctrl: false,
+        shift: false,
+        alt: false,
+    };
+    for modifier in parts {
+        match modifier {
+            "ctrl" | "control" => binding.ctrl = true,
+            "shift" => binding.shift = true,
+            "alt" => binding.alt = true,
+            _ => return None,
+        }
+    }
+    Some(binding)
+}
+
+fn key_code(name: &str) -> Option<KeyCode> {
+    let code = match name {
+        "a" => KeyCode::A,
+        "b" => KeyCode::B,
+        "c" => KeyCode::C,
+        "d" => KeyCode::D,
+        "e" => KeyCode::E,
+        "f" => KeyCode::F,
+        "g" => KeyCode::G,
+        "h" => KeyCode::H,
+        "i" => KeyCode::I,
+        "j" => KeyCode::J,
+        "k" => KeyCode::K,
+        "l" => KeyCode::L,
+        "m" => KeyCode::M,
+        "n" => KeyCode::N,
+        "o" => KeyCode::O,
+        "p" => KeyCode::P,
+        "q" => KeyCode::Q,
+        "r" => KeyCode::R,
+        "s" => KeyCode::S,
+        "t" => KeyCode::T,
+        "u" => KeyCode::U,
+        "v" => KeyCode::V,
+        "w" => KeyCode::W,
+        "x" => KeyCode::X,
+        "y" => KeyCode::Y,
+        "z" => KeyCode::Z,
+        "0" => KeyCode::Key0,
+        "1" => KeyCode::Key1,
+        "2" => KeyCode::Key2,
+        "3" => KeyCode::Key3,
+        "4" => KeyCode::Key4,
+        "5" => KeyCode::Key5,
+        "6" => KeyCode::Key6,
+        "7" => KeyCode::Key7,
+        "8" => KeyCode::Key8,
+        "9" => KeyCode::Key9,
+        "f1" => KeyCode::F1,
+        "f2" => KeyCode::F2,
+        "f3" => KeyCode::F3,
+        "f4" => KeyCode::F4,
+        "f5" => KeyCode::F5,
+        "f6" => KeyCode::F6,
+        "f7" => KeyCode::F7,
+        "f8" => KeyCode::F8,
+        "f9" => KeyCode::F9,
+        "f10" => KeyCode::F10,
+        "f11" => KeyCode::F11,
+        "f12" => KeyCode::F12,
+        "return" | "enter" => KeyCode::Return,
+        "space" => KeyCode::Space,
+        "escape" | "esc" => KeyCode::Escape,
+        "tab" => KeyCode::Tab,
+        "back" | "backspace" => KeyCode::Back,
+        "delete" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "minus" => KeyCode::Minus,
+        "equals" => KeyCode::Equals,
+        "comma" => KeyCode::Comma,
+        "period" => KeyCode::Period,
+        "slash" => KeyCode::Slash,
+        _ => return None,
+    };
+    Some(code)
+}
@@ -0,0 +1,23 @@
+//! Everything about the game that does not need a window, shared by the
+//! `ticktacktoe` binary and its tests. The rules themselves live in the
+//! `tickticktoe-engine` crate, re-exported here.
+
+pub use tickticktoe_engine::{
+    ai, ascii, game, observer, puzzle, record, replay, review, rng, snapshots,
+};
+
+pub mod analysis;
+pub mod animation;
+pub mod bench;
+pub mod clock;
+pub mod headless;
+pub mod keymap;
+pub mod menu;
+pub mod replay_file;
+pub mod score;
+pub mod settings;
+pub mod stats;
+pub mod svg;
+pub mod theme;
+pub mod trainer;
+pub mod viewer;
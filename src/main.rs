@@ -1,230 +1,2466 @@
 #![windows_subsystem = "windows"]
 
 use ggez;
+use ggez::conf::NumSamples;
 use ggez::event::KeyCode;
-use ggez::event::{self, MouseButton};
+use ggez::event::{self, Button, MouseButton};
+use ggez::filesystem;
+use ggez::graphics::spritebatch::SpriteBatch;
 use ggez::graphics::{self, DrawMode, MeshBuilder};
 use ggez::input::keyboard::KeyMods;
 use ggez::timer;
 use ggez::Context;
+use std::fs::OpenOptions;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-enum Player {
-    Naughts,
-    Crosses,
+use ticktacktoe::analysis::{self, Analyst, Hinter, Reviewer};
+use ticktacktoe::clock::{self, Clock, TimeControl};
+use ticktacktoe::game::{column_name, Axis, Game, Phase, Player};
+use ticktacktoe::keymap::{Command, Keymap};
+use ticktacktoe::menu::{Action, Menu, Page};
+use ticktacktoe::puzzle::{Generator, Puzzle, Verdict};
+use ticktacktoe::record::Record;
+use ticktacktoe::replay::Replay;
+use ticktacktoe::replay_file::ReplayFile;
+use ticktacktoe::review::{self, Review};
+use ticktacktoe::score::Score;
+use ticktacktoe::settings::{self, FirstMove, Settings};
+use ticktacktoe::snapshots::Snapshots;
+use ticktacktoe::stats::Stats;
+use ticktacktoe::theme::{self, Marks, Mode, Palette, Shape, Theme};
+use ticktacktoe::trainer::{Opening, Trainer};
+use ticktacktoe::viewer::Viewer;
+use ticktacktoe::{animation, ascii, bench, headless, svg};
+
+/// Height of the strips above and below the board that hold the score and
+/// the player labels.
+const HUD_HEIGHT: f32 = 32.0;
+
+/// Size of overlaid text, before scaling for hi-dpi displays.
+const TEXT_SIZE: f32 = 16.0;
+
+/// How much larger the HUD is drawn in streamer overlay mode, so names,
+/// scores and clocks stay legible when the capture is scaled down.
+const OVERLAY_SCALE: f32 = 2.0;
+
+/// Background of streamer overlays, to be keyed out by the capture software.
+const CHROMA_KEY: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
+
+/// Margin between overlaid text and the window edges.
+const MARGIN: f32 = 8.0;
+
+/// How long a notice stays on screen.
+const NOTICE_DURATION: Duration = Duration::from_secs(2);
+
+/// How long a suggested move stays on screen.
+const HINT_DURATION: Duration = Duration::from_secs(2);
+
+/// Directory, within the user data directory, that screenshots are saved to.
+const SCREENSHOT_DIR: &str = "/screenshots";
+
+/// Directory, within the user data directory, that exported boards are saved
+/// to.
+const EXPORT_DIR: &str = "/exports";
+
+/// Background of error notices.
+const ERROR_COLOR: [f32; 4] = [0.8, 0.1, 0.1, 0.9];
+
+/// File, within the user data directory, that Ctrl+S saves the game to and
+/// Ctrl+O loads it from.
+const SAVE_FILE: &str = "/saved-game.toml";
+
+/// How many states of the game are kept for stepping back through in debug
+/// mode.
+const SNAPSHOT_CAPACITY: usize = 100;
+
+/// File, within the user data directory, that the game in progress is saved
+/// to every few moves, so it can be restored if the game exits unexpectedly.
+const AUTOSAVE_FILE: &str = "/autosave.toml";
+
+/// Number of moves between autosaves.
+const AUTOSAVE_MOVES: usize = 3;
+
+/// Directory, within the user data directory, of puzzles added to the daily
+/// puzzle pool alongside the built-in ones.
+const PUZZLE_DIR: &str = "/puzzles";
+
+/// How long to sleep between updates while there is nothing to redraw.
+const IDLE_SLEEP: Duration = Duration::from_millis(10);
+
+/// Furthest the board can be zoomed in, as a multiple of its fitted size.
+const MAX_ZOOM: f32 = 8.0;
+
+/// Boards with more columns than this draw their pieces as images in a sprite
+/// batch, rather than tessellating every mark into the pieces mesh, so that
+/// boards with thousands of pieces stay smooth.
+const BATCH_SIZE: usize = 19;
+
+// What the mark images drawn on large boards depend on: the mark radius and
+// stroke in pixels, and each player's color and shape.
+type StampKey = (f32, f32, [graphics::Color; 2], [Shape; 2]);
+
+/// Factor the zoom changes by for each step of the mouse wheel.
+const ZOOM_STEP: f32 = 1.1;
+
+// A message shown briefly in the corner of the window.
+struct Notice {
+    message: String,
+    shown: Instant,
+    // Errors are shown as a toast that stands out from the board.
+    error: bool,
+}
+
+// Options chosen on the command line.
+struct Options {
+    size: usize,
+    win: usize,
+    gravity: bool,
+    best_of: Option<usize>,
+    names: (String, String),
+    replay_delay: Duration,
+    // Time each player has for a round, if the round is timed.
+    clock: Option<TimeControl>,
+    // How long after a round ends the next starts on its own, if it does.
+    next_round: Option<Duration>,
+    // Frames drawn per second at most, if limited.
+    max_fps: Option<u32>,
+    // Whether to draw for capture as a stream overlay.
+    overlay: bool,
+    // Where moves and results are announced as text, if anywhere.
+    narrator: Option<Box<dyn Write>>,
+    // A replay to open the viewer on at startup, if any.
+    replay: Option<Replay>,
+    // A puzzle to set on the board at startup, if any.
+    puzzle: Option<Puzzle>,
+    // Why the settings file could not be read, if it could not.
+    settings_error: Option<String>,
+    // Why the remembered theme could not be loaded, if it could not.
+    theme_error: Option<String>,
 }
 
-impl Player {
-    fn color(&self) -> graphics::Color {
-        match self {
-            Player::Naughts => [1.0, 0.647, 0.0, 1.0].into(),
-            Player::Crosses => [0.0, 0.35, 1.0, 1.0].into(),
-        }
+struct MainState {
+    game: Game,
+    // The theme as loaded from the config file, from which `theme` is derived
+    // according to the current mode.
+    base_theme: Theme,
+    theme: Theme,
+    settings: Settings,
+    score: Score,
+    // Move count and timings of the live round.
+    stats: Stats,
+    names: (String, String),
+    // Cell selected with the arrow keys. Hidden until the keyboard is used and
+    // again whenever the mouse is.
+    cursor: Option<(usize, usize)>,
+    // Direction the gamepad stick is held in on each axis, so that holding
+    // the stick moves the cursor once rather than on every axis event.
+    stick: (i32, i32),
+    // Whether anything has changed since the last frame was presented.
+    dirty: bool,
+    // Grid lines, along with the board area and color they were built for.
+    grid_mesh: Option<((graphics::Rect, graphics::Color), graphics::Mesh)>,
+    // Pieces on the board, rebuilt only when `pieces_stale` is set.
+    pieces_mesh: Option<graphics::Mesh>,
+    pieces_stale: bool,
+    // Naughts and crosses drawn from images, one batch each, for skins and
+    // large boards. Rebuilt along with the pieces mesh.
+    batches: Option<(SpriteBatch, SpriteBatch)>,
+    // Images of the theme's marks drawn in the batches when there is no
+    // skin, along with what they were drawn for.
+    stamps: Option<(StampKey, (graphics::Image, graphics::Image))>,
+    // Replay being stepped through in place of the live game, if any.
+    viewer: Option<Viewer>,
+    // Time between moves when autoplaying a replay.
+    replay_delay: Duration,
+    // Message shown briefly in the corner of the window, and when it was
+    // shown.
+    notice: Option<Notice>,
+    clock: Option<Clock>,
+    // How long after a round ends the next starts on its own, if it does,
+    // and when the round on the board ended.
+    next_round: Option<Duration>,
+    round_over: Option<Instant>,
+    // Move suggested to the player to move, and when it was suggested.
+    hint: Option<((usize, usize), Instant)>,
+    // The search for a hint, while it runs.
+    hinter: Option<Hinter>,
+    // Naughts and crosses images from the theme's skin, if it has one.
+    sprites: Option<(graphics::Image, graphics::Image)>,
+    // Window title last set, so it is only updated when the status changes.
+    title: String,
+    // Start screen shown in place of the board, if open.
+    menu: Option<Menu>,
+    // Cell chosen but not yet confirmed, when moves need confirming.
+    pending: Option<(usize, usize)>,
+    // Camera over the board: how far it is zoomed in, and how far the zoomed
+    // board is moved from the center of the window, in pixels.
+    zoom: f32,
+    pan: (f32, f32),
+    // Whether the board is being dragged with the middle mouse button.
+    panning: bool,
+    // Whether the debug overlay is shown, and how many times the cached
+    // meshes have been rebuilt, for diagnosing performance.
+    debug: bool,
+    rebuilds: usize,
+    // Shortest time between frames when the frame rate is limited, and when
+    // the last frame was presented.
+    frame_time: Option<Duration>,
+    last_frame: Instant,
+    // Game left in the autosave by a session that did not exit cleanly.
+    recovery: Option<Replay>,
+    // Number of moves of the live game in the last autosave.
+    autosaved: usize,
+    // Where moves and results are announced as text, with the board after
+    // each, for screen readers and logs.
+    narrator: Option<Box<dyn Write>>,
+    // Commands bound to keys in the settings.
+    keymap: Keymap,
+    // Recent states of the live game, stepped through with the snapshot keys
+    // in debug mode.
+    snapshots: Snapshots,
+    // Whether the game is drawn as a streamer overlay, on a chroma key
+    // background with a larger HUD.
+    overlay: bool,
+    // Puzzle being solved on the board, if any, and whether a move has
+    // already let its win slip.
+    puzzle: Option<Puzzle>,
+    puzzle_failed: bool,
+    // Opening quiz on the board, if any.
+    trainer: Option<Trainer>,
+    // The local game set aside while a puzzle or quiz is on the board.
+    local: Option<Game>,
+    // Whether the evaluation bar and best continuations are shown, and the
+    // analysis of the position on the board.
+    analysing: bool,
+    analyst: Option<Analyst>,
+    // The review under way, if any.
+    reviewer: Option<Reviewer>,
+}
+
+impl MainState {
+    fn new(
+        ctx: &mut Context,
+        options: Options,
+        theme: Theme,
+        settings: Settings,
+    ) -> ggez::GameResult<MainState> {
+        let sprites = match &theme.skin {
+            Some(skin) => Some((
+                graphics::Image::new(ctx, &skin.naughts)?,
+                graphics::Image::new(ctx, &skin.crosses)?,
+            )),
+            None => None,
+        };
+        let recovery = if filesystem::exists(ctx, AUTOSAVE_FILE) {
+            Replay::load(ctx, AUTOSAVE_FILE)
+                .ok()
+                .filter(|replay| !replay.moves.is_empty() && replay.end().in_progress())
+        } else {
+            None
+        };
+        let (keymap, keymap_error) = match Keymap::new(&settings.keys) {
+            Ok(keymap) => (keymap, None),
+            Err(err) => (Keymap::default(), Some(err)),
+        };
+        let replay_delay = options.replay_delay;
+        let settings_error = options.settings_error;
+        let theme_error = options.theme_error;
+        // Overlays, replays and puzzles go straight to the board.
+        let menu = if options.overlay || options.replay.is_some() || options.puzzle.is_some() {
+            None
+        } else {
+            Some(Menu::new())
+        };
+        let mut s = MainState {
+            // A replay given on the command line replaces the board's rules,
+            // and the board is left at its final position.
+            game: match &options.replay {
+                Some(replay) => replay.end(),
+                None => Game::new(options.size, options.win, options.gravity),
+            },
+            theme: mode_theme(&theme, settings.mode, options.overlay),
+            base_theme: theme,
+            settings: settings,
+            score: Score::new(options.best_of),
+            stats: Stats::new(),
+            names: options.names,
+            cursor: None,
+            stick: (0, 0),
+            dirty: true,
+            grid_mesh: None,
+            pieces_mesh: None,
+            pieces_stale: true,
+            batches: None,
+            stamps: None,
+            viewer: options
+                .replay
+                .map(|replay| Viewer::new(replay, replay_delay)),
+            replay_delay: options.replay_delay,
+            notice: None,
+            clock: options.clock.map(Clock::new),
+            next_round: options.next_round,
+            round_over: None,
+            hint: None,
+            hinter: None,
+            sprites: sprites,
+            title: String::new(),
+            menu: menu,
+            pending: None,
+            zoom: 1.0,
+            pan: (0.0, 0.0),
+            panning: false,
+            debug: false,
+            rebuilds: 0,
+            frame_time: options
+                .max_fps
+                .map(|fps| Duration::from_secs(1) / fps.max(1)),
+            last_frame: Instant::now(),
+            recovery: recovery,
+            autosaved: 0,
+            narrator: options.narrator,
+            keymap: keymap,
+            snapshots: Snapshots::new(SNAPSHOT_CAPACITY),
+            overlay: options.overlay,
+            puzzle: None,
+            puzzle_failed: false,
+            trainer: None,
+            local: None,
+            analysing: false,
+            analyst: None,
+            reviewer: None,
+        };
+        s.snapshots.record(&s.game);
+        if let Some(puzzle) = options.puzzle {
+            s.start_puzzle(puzzle);
+        }
+        if let Some(err) = keymap_error {
+            s.warn(format!("Using the default keys: {}", err));
+        }
+        if let Some(err) = settings_error {
+            s.warn(format!("Using the default settings: {}", err));
+        }
+        if let Some(err) = theme_error {
+            s.warn(format!("Using the default theme: {}", err));
+        }
+        Ok(s)
+    }
+
+    fn restart(&mut self) {
+        if let Some(puzzle) = &self.puzzle {
+            let game = puzzle.game();
+            self.puzzle_failed = false;
+            self.resume(game);
+            return;
+        }
+        if let Some(trainer) = &mut self.trainer {
+            trainer.next();
+            let game = trainer.question.clone();
+            self.resume(game);
+            self.ask();
+            return;
+        }
+        if self.score.match_winner().is_some() {
+            self.score.reset();
+        }
+        let first = match self.settings.first_move {
+            FirstMove::Alternate => self.game.first.other(),
+            FirstMove::Naughts => Player::Naughts,
+        };
+        self.game.restart(first);
+        self.stats.reset();
+        self.hint = None;
+        self.hinter = None;
+        self.pending = None;
+        if let Some(clock) = &mut self.clock {
+            clock.reset();
+        }
+        self.snapshots.record(&self.game);
+        self.narrate(format!("New round. {} moves first.", self.name(first)));
+        self.changed();
+    }
+
+    fn toggle_mode(&mut self, ctx: &mut Context) {
+        self.settings.mode = self.settings.mode.toggle();
+        self.theme = mode_theme(&self.base_theme, self.settings.mode, self.overlay);
+        self.changed();
+        self.save_settings(ctx);
+    }
+
+    // The game being drawn: the replay position while viewing a replay,
+    // otherwise the live game.
+    fn shown(&self) -> &Game {
+        self.viewer
+            .as_ref()
+            .map(|viewer| &viewer.game)
+            .or_else(|| self.snapshots.inspected())
+            .unwrap_or(&self.game)
+    }
+
+    // Marks the board as changed so that the pieces are rebuilt and the frame
+    // redrawn.
+    fn changed(&mut self) {
+        self.dirty = true;
+        self.pieces_stale = true;
+    }
+
+    // Opens the moves played so far in the replay viewer, or returns to the
+    // live game.
+    fn toggle_viewer(&mut self) {
+        self.viewer = match self.viewer {
+            Some(_) => None,
+            None => Some(Viewer::new(
+                Replay::from_game(&self.game),
+                self.replay_delay,
+            )),
+        };
+        self.changed();
+    }
+
+    // Handles a key press while viewing a replay, returning whether the key was
+    // used by the viewer.
+    fn viewer_key(&mut self, command: Option<Command>) -> bool {
+        let viewer = match &mut self.viewer {
+            Some(viewer) => viewer,
+            None => return false,
+        };
+        match command {
+            Some(Command::Left) => {
+                viewer.back();
+            }
+            Some(Command::Right) => {
+                viewer.forward();
+            }
+            Some(Command::ReplayStart) => viewer.seek(0),
+            Some(Command::ReplayEnd) => viewer.seek(viewer.len()),
+            Some(Command::Autoplay) => viewer.toggle_autoplay(),
+            Some(Command::NextMistake) => {
+                viewer.next_mistake();
+            }
+            _ => return false,
+        }
+        self.changed();
+        true
+    }
+
+    // Shows the start screen, pausing the clock until play resumes.
+    fn open_menu(&mut self) {
+        self.menu = Some(Menu::new());
+        if let Some(clock) = &mut self.clock {
+            clock.stop();
+        }
+        self.dirty = true;
+    }
+
+    fn activate(&mut self, ctx: &mut Context, action: Action) {
+        match action {
+            Action::Play => {
+                self.menu = None;
+                self.recovery = None;
+                if let Some(game) = self.local.take() {
+                    self.puzzle = None;
+                    self.trainer = None;
+                    self.resume(game);
+                }
+                self.update_clock();
+                self.changed();
+            }
+            Action::Recover => {
+                self.menu = None;
+                if let Some(replay) = self.recovery.take() {
+                    self.leave_puzzle();
+                    self.resume(replay.end());
+                }
+            }
+            Action::Puzzle => {
+                self.menu = None;
+                self.start_puzzle(daily_puzzle(ctx));
+            }
+            Action::Train(opening) => {
+                self.menu = None;
+                self.start_trainer(opening);
+            }
+            Action::Settings => {
+                if let Some(menu) = &mut self.menu {
+                    menu.open(Page::Settings);
+                }
+            }
+            Action::Back => {
+                if let Some(menu) = &mut self.menu {
+                    menu.open(Page::Main);
+                }
+            }
+            Action::ToggleMode => self.toggle_mode(ctx),
+            Action::ToggleConfirm => {
+                self.settings.confirm_moves = !self.settings.confirm_moves;
+                self.pending = None;
+                self.save_settings(ctx);
+            }
+            Action::ToggleFirstMove => {
+                self.settings.first_move = self.settings.first_move.toggle();
+                // A round nobody has moved in yet takes the change straight
+                // away.
+                if self.settings.first_move == FirstMove::Naughts {
+                    self.game.set_first(Player::Naughts);
+                }
+                self.save_settings(ctx);
+            }
+            Action::Quit => {
+                self.save_session(ctx);
+                event::quit(ctx);
+            }
+        }
+        self.dirty = true;
+    }
+
+    // The action of the highlighted menu entry.
+    fn menu_action(&self) -> Option<Action> {
+        let menu = self.menu.as_ref()?;
+        let entries = menu.entries(&self.settings, self.recovery.is_some());
+        entries.get(menu.selected).map(|(_, action)| *action)
+    }
+
+    // Moves the menu highlight by `delta` entries.
+    fn menu_step(&mut self, delta: i32) {
+        let count = match &self.menu {
+            Some(menu) => menu.entries(&self.settings, self.recovery.is_some()).len(),
+            None => return,
+        };
+        if let Some(menu) = &mut self.menu {
+            menu.step(delta, count);
+        }
+        self.dirty = true;
+    }
+
+    // Handles a key release while the menu is open, returning whether the
+    // menu is open. Releases are used so that the key which opens or closes the
+    // menu is not seen again by the board.
+    fn menu_key(&mut self, ctx: &mut Context, command: Option<Command>) -> bool {
+        let page = match &self.menu {
+            Some(menu) => menu.page,
+            None => return false,
+        };
+        match command {
+            Some(Command::Up) => self.menu_step(-1),
+            Some(Command::Down) => self.menu_step(1),
+            Some(Command::Place) | Some(Command::Restart) => {
+                if let Some(action) = self.menu_action() {
+                    self.activate(ctx, action);
+                }
+            }
+            Some(Command::Back) if page == Page::Main => self.activate(ctx, Action::Quit),
+            Some(Command::Back) => self.activate(ctx, Action::Back),
+            _ => {}
+        }
+        true
+    }
+
+    // The area of the window taken by the given menu entry. Entries are
+    // stacked in the middle of the window, below the title.
+    fn menu_entry_rect(&self, ctx: &ggez::Context, index: usize, count: usize) -> graphics::Rect {
+        let (w, h) = graphics::drawable_size(ctx);
+        let height = TEXT_SIZE * 2.5 * dpi_scale(ctx);
+        let top = h / 2.0 - height * count as f32 / 2.0 + height;
+        graphics::Rect::new(w / 4.0, top + height * index as f32, w / 2.0, height)
+    }
+
+    fn menu_entry_at(&self, ctx: &ggez::Context, x: f32, y: f32) -> Option<usize> {
+        let count = self
+            .menu
+            .as_ref()?
+            .entries(&self.settings, self.recovery.is_some())
+            .len();
+        (0..count).find(|index| self.menu_entry_rect(ctx, *index, count).contains([x, y]))
+    }
+
+    fn draw_menu(&self, ctx: &mut ggez::Context) -> ggez::GameResult {
+        let menu = match &self.menu {
+            Some(menu) => menu,
+            None => return Ok(()),
+        };
+        let entries = menu.entries(&self.settings, self.recovery.is_some());
+        let count = entries.len();
+        let (w, _) = graphics::drawable_size(ctx);
+        let text_color: graphics::Color = self.theme.text.into();
+        let first = self.menu_entry_rect(ctx, 0, count);
+        let mut title = graphics::Text::new("Tick Tack Toe");
+        title.set_font(
+            graphics::Font::default(),
+            graphics::Scale::uniform(TEXT_SIZE * 3.0 * dpi_scale(ctx)),
+        );
+        let (title_w, title_h) = (title.width(ctx) as f32, title.height(ctx) as f32);
+        let param = graphics::DrawParam::default()
+            .dest([(w - title_w) / 2.0, first.y - title_h * 2.0])
+            .color(text_color);
+        graphics::draw(ctx, &title, param)?;
+        let mut highlight = self.theme.player_color(Player::Naughts);
+        highlight.a *= 0.3;
+        let selected = self.menu_entry_rect(ctx, menu.selected, count);
+        let mesh = MeshBuilder::new()
+            .rectangle(DrawMode::fill(), selected, highlight)
+            .build(ctx)?;
+        graphics::draw(ctx, &mesh, graphics::DrawParam::default())?;
+        for (index, (label, _)) in entries.into_iter().enumerate() {
+            let rect = self.menu_entry_rect(ctx, index, count);
+            let mut text = graphics::Text::new(label);
+            text.set_font(
+                graphics::Font::default(),
+                graphics::Scale::uniform(TEXT_SIZE * 1.5 * dpi_scale(ctx)),
+            );
+            let (text_w, text_h) = (text.width(ctx) as f32, text.height(ctx) as f32);
+            let param = graphics::DrawParam::default()
+                .dest([
+                    rect.x + (rect.w - text_w) / 2.0,
+                    rect.y + (rect.h - text_h) / 2.0,
+                ])
+                .color(text_color);
+            graphics::draw(ctx, &text, param)?;
+        }
+        Ok(())
+    }
+
+    // Saves the settings along with the window's size and position, to be
+    // restored next session.
+    fn save_session(&mut self, ctx: &mut Context) {
+        self.settings.remember_window(ctx);
+        if let Err(err) = self.settings.save(ctx) {
+            eprintln!("saving settings: {}", err);
+        }
+        // The session ended cleanly, so there is nothing to recover.
+        if filesystem::exists(ctx, AUTOSAVE_FILE) {
+            if let Err(err) = filesystem::delete(ctx, AUTOSAVE_FILE) {
+                eprintln!("removing autosave: {}", err);
+            }
+        }
+    }
+
+    // Saves the live game every few moves while it is in progress, and removes
+    // the save once the round is over.
+    fn autosave(&mut self, ctx: &mut Context) -> ggez::GameResult {
+        // Practice positions are not worth restoring, and the autosave still
+        // belongs to the local game set aside for them.
+        if self.practising() {
+            return Ok(());
+        }
+        let moves = self.game.history.len();
+        if moves == self.autosaved {
+            return Ok(());
+        }
+        if self.game.phase() != Phase::Playing {
+            self.autosaved = moves;
+            if filesystem::exists(ctx, AUTOSAVE_FILE) {
+                filesystem::delete(ctx, AUTOSAVE_FILE)?;
+            }
+        } else if moves % AUTOSAVE_MOVES == 0 || moves < self.autosaved {
+            self.autosaved = moves;
+            Replay::from_game(&self.game).save(ctx, AUTOSAVE_FILE)?;
+        }
+        Ok(())
+    }
+
+    fn save_settings(&mut self, ctx: &mut Context) {
+        if let Err(err) = self.settings.save(ctx) {
+            self.warn(format!("Saving settings failed: {}", err));
+        }
+    }
+
+    fn notify(&mut self, message: String) {
+        self.notice = Some(Notice {
+            message: message,
+            shown: Instant::now(),
+            error: false,
+        });
+        self.dirty = true;
+    }
+
+    // Shows an error as a toast rather than dropping it.
+    fn warn(&mut self, message: String) {
+        self.notice = Some(Notice {
+            message: message,
+            shown: Instant::now(),
+            error: true,
+        });
+        self.dirty = true;
+    }
+
+    // Saves the last presented frame as a PNG named after the current time.
+    fn screenshot(&mut self, ctx: &mut Context) -> ggez::GameResult<String> {
+        let path = format!("{}/tickticktoe-{}.png", SCREENSHOT_DIR, timestamp());
+        filesystem::create_dir(ctx, SCREENSHOT_DIR)?;
+        graphics::screenshot(ctx)?.encode(ctx, graphics::ImageFormat::Png, &path)?;
+        Ok(path)
+    }
+
+    // Saves the board being shown as an SVG named after the current time.
+    fn export_svg(&mut self, ctx: &mut Context) -> ggez::GameResult<String> {
+        let path = format!("{}/tickticktoe-{}.svg", EXPORT_DIR, timestamp());
+        filesystem::create_dir(ctx, EXPORT_DIR)?;
+        let svg = svg::render(self.shown(), &self.theme);
+        filesystem::create(ctx, &path)?.write_all(svg.as_bytes())?;
+        Ok(path)
+    }
+
+    // Saves the game record, named after the current time. While the viewer
+    // is open the replay being viewed is exported, otherwise the live game.
+    // A reviewed replay is exported as its review instead.
+    fn export_record(&mut self, ctx: &mut Context) -> ggez::GameResult<String> {
+        let path = format!("{}/tickticktoe-{}.txt", EXPORT_DIR, timestamp());
+        let text = match &self.viewer {
+            Some(viewer) => match &viewer.review {
+                Some(review) => review.to_string(),
+                None => Record::from_game(&viewer.replay.end(), &self.names).to_string(),
+            },
+            None => Record::from_game(&self.game, &self.names).to_string(),
+        };
+        filesystem::create_dir(ctx, EXPORT_DIR)?;
+        filesystem::create(ctx, &path)?.write_all(text.as_bytes())?;
+        Ok(path)
+    }
+
+    fn save_game(&mut self, ctx: &mut Context) -> ggez::GameResult {
+        Replay::from_game(&self.game).save(ctx, SAVE_FILE)
+    }
+
+    // Resumes the game saved with `save_game`, with the clocks refilled.
+    fn load_game(&mut self, ctx: &mut Context) -> ggez::GameResult {
+        let replay = Replay::load(ctx, SAVE_FILE)?;
+        self.leave_puzzle();
+        self.resume(replay.end());
+        Ok(())
+    }
+
+    // Replaces the live game, clearing anything that belonged to the old one.
+    fn resume(&mut self, game: Game) {
+        self.game = game;
+        self.stats.reset();
+        self.viewer = None;
+        self.reviewer = None;
+        self.cursor = None;
+        self.hint = None;
+        self.hinter = None;
+        self.pending = None;
+        if let Some(clock) = &mut self.clock {
+            clock.reset();
+        }
+        self.update_clock();
+        self.reset_camera();
+        self.snapshots.record(&self.game);
+        self.narrate("Game restored.".to_owned());
+        self.changed();
+    }
+
+    // Announces an event followed by the result, if it ended the round, and
+    // the board as text. Narration stops if it cannot be written.
+    fn narrate(&mut self, event: String) {
+        if self.narrator.is_none() {
+            return;
+        }
+        let game = &self.game;
+        let result = match game.victor() {
+            Some(player) => format!(" {} wins.", self.name(player)),
+            None if game.drawn => " The round is drawn.".to_owned(),
+            None => String::new(),
+        };
+        let stats = if game.in_progress() {
+            String::new()
+        } else {
+            format!(" {}.", self.stats.summary())
+        };
+        let text = format!("{}{}{}\n{}\n", event, result, stats, ascii::render(game));
+        let written = match &mut self.narrator {
+            Some(narrator) => narrator
+                .write_all(text.as_bytes())
+                .and_then(|_| narrator.flush()),
+            None => Ok(()),
+        };
+        if let Err(err) = written {
+            self.narrator = None;
+            self.warn(format!("Narration stopped: {}", err));
+        }
+    }
+
+    // Saves the game as an animated GIF named after the current time. While
+    // the viewer is open the replay being viewed is exported, otherwise the
+    // live game.
+    fn export_gif(&mut self, ctx: &mut Context) -> ggez::GameResult<String> {
+        let path = format!("{}/tickticktoe-{}.gif", EXPORT_DIR, timestamp());
+        let replay = match &self.viewer {
+            Some(viewer) => viewer.replay.clone(),
+            None => Replay::from_game(&self.game),
+        };
+        filesystem::create_dir(ctx, EXPORT_DIR)?;
+        let file = filesystem::create(ctx, &path)?;
+        animation::render(file, &replay, &self.theme, self.replay_delay)?;
+        Ok(path)
+    }
+
+    // Status of the game for the window title, so it can be followed from the
+    // taskbar or while switching windows.
+    fn title(&self) -> String {
+        if self.menu.is_some() {
+            return "Tick Tack Toe".to_owned();
+        }
+        let status = match &self.viewer {
+            Some(viewer) => format!("Replay, move {} of {}", viewer.position, viewer.len()),
+            None => {
+                let game = &self.game;
+                let (naughts, crosses) = (self.score.naughts, self.score.crosses);
+                let tally = |player: Player| match player {
+                    Player::Naughts => format!("{}\u{2013}{}", naughts, crosses),
+                    Player::Crosses => format!("{}\u{2013}{}", crosses, naughts),
+                };
+                match game.victor() {
+                    Some(player) if game.timed_out.is_some() => {
+                        format!("{} wins on time {}", self.name(player), tally(player))
+                    }
+                    Some(player) => format!("{} wins {}", self.name(player), tally(player)),
+                    None if game.drawn => format!("Draw {}", tally(Player::Naughts)),
+                    None => format!("{} to move", self.name(game.turn)),
+                }
+            }
+        };
+        format!("Tick Tack Toe \u{2014} {}", status)
+    }
+
+    fn name(&self, player: Player) -> &str {
+        match player {
+            Player::Naughts => &self.names.0,
+            Player::Crosses => &self.names.1,
+        }
+    }
+
+    // Scale of the HUD relative to its base size, taking in the display's
+    // pixel density and whether the game is drawn as an overlay.
+    fn hud_scale(&self, ctx: &ggez::Context) -> f32 {
+        if self.overlay {
+            dpi_scale(ctx) * OVERLAY_SCALE
+        } else {
+            dpi_scale(ctx)
+        }
+    }
+
+    // The area of the window the whole board fits in. The board is kept square
+    // and centered in the space left between the HUD strips, so that pieces
+    // are not stretched when the window is not.
+    fn fitted_rect(&self, ctx: &ggez::Context) -> graphics::Rect {
+        let (w, h) = graphics::drawable_size(ctx);
+        let hud = HUD_HEIGHT * self.hud_scale(ctx);
+        let available = (h - hud * 2.0).max(0.0);
+        let side = w.min(available);
+        graphics::Rect::new((w - side) / 2.0, hud + (available - side) / 2.0, side, side)
+    }
+
+    // The area the board is drawn in: the fitted area scaled about its center
+    // by the zoom, then moved by the pan. Drawing and hit-testing both go
+    // through here, so they always agree.
+    fn board_rect(&self, ctx: &ggez::Context) -> graphics::Rect {
+        let fitted = self.fitted_rect(ctx);
+        let side = fitted.w * self.zoom;
+        graphics::Rect::new(
+            fitted.x + (fitted.w - side) / 2.0 + self.pan.0,
+            fitted.y + (fitted.h - side) / 2.0 + self.pan.1,
+            side,
+            side,
+        )
+    }
+
+    // Zooms by `factor` about the given point, keeping the part of the board
+    // under it in place.
+    fn zoom_at(&mut self, ctx: &ggez::Context, factor: f32, (x, y): (f32, f32)) {
+        let zoom = (self.zoom * factor).max(1.0).min(MAX_ZOOM);
+        let fitted = self.fitted_rect(ctx);
+        let center = (fitted.x + fitted.w / 2.0, fitted.y + fitted.h / 2.0);
+        let scale = zoom / self.zoom;
+        self.pan = (
+            x - center.0 - (x - center.0 - self.pan.0) * scale,
+            y - center.1 - (y - center.1 - self.pan.1) * scale,
+        );
+        self.zoom = zoom;
+        self.clamp_pan(ctx);
+        self.changed();
+    }
+
+    // Keeps the zoomed board covering the area it fits in, so it cannot be
+    // dragged out of view.
+    fn clamp_pan(&mut self, ctx: &ggez::Context) {
+        let fitted = self.fitted_rect(ctx);
+        let limit = fitted.w * (self.zoom - 1.0) / 2.0;
+        self.pan = (
+            self.pan.0.max(-limit).min(limit),
+            self.pan.1.max(-limit).min(limit),
+        );
+    }
+
+    fn reset_camera(&mut self) {
+        self.zoom = 1.0;
+        self.pan = (0.0, 0.0);
+        self.changed();
+    }
+
+    fn build_grid(&self, ctx: &ggez::Context, mb: &mut MeshBuilder) -> ggez::GameResult {
+        let game = self.shown();
+        let board = self.board_rect(ctx);
+        let color = self.theme.grid_color(game.turn);
+        let column_width = board.w / game.size as f32;
+        let stroke = theme::stroke(self.theme.grid_stroke, column_width);
+        for ii in 1..game.size {
+            let offset = board.x + column_width * ii as f32;
+            mb.line(
+                &[[offset, board.y], [offset, board.y + board.h]],
+                stroke,
+                color,
+            )?;
+        }
+        let row_height = board.h / game.size as f32;
+        for ii in 1..game.size {
+            let offset = board.y + row_height * ii as f32;
+            mb.line(
+                &[[board.x, offset], [board.x + board.w, offset]],
+                stroke,
+                color,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn build_players(&self, ctx: &ggez::Context, mb: &mut MeshBuilder) -> ggez::GameResult {
+        let game = self.shown();
+        let board = self.board_rect(ctx);
+        let column_width = board.w / game.size as f32;
+        let row_height = board.h / game.size as f32;
+        // Shade the cell of the last move so it stands out at a glance.
+        if let Some((col, row)) = game.history.last() {
+            if let Some(player) = game.grid.get(*col, *row) {
+                let cell = graphics::Rect::new(
+                    board.x + column_width * *col as f32,
+                    board.y + row_height * *row as f32,
+                    column_width,
+                    row_height,
+                );
+                let mut color = self.theme.player_color(player);
+                color.a *= 0.2;
+                mb.rectangle(DrawMode::fill(), cell, color);
+            }
+        }
+        // Batched pieces are drawn from images rather than the mesh.
+        if self.batched() {
+            return Ok(());
+        }
+        for (cell, player) in game.grid.pieces() {
+            let color = self.theme.player_color(player);
+            self.build_piece(ctx, mb, cell, player, color)?;
+        }
+        Ok(())
+    }
+
+    // The center of the given cell and the radius of the mark drawn in it.
+    fn piece_geometry(&self, ctx: &ggez::Context, (col, row): (usize, usize)) -> ([f32; 2], f32) {
+        let board = self.board_rect(ctx);
+        let column_width = board.w / self.shown().size as f32;
+        let row_height = board.h / self.shown().size as f32;
+        let center = [
+            board.x + column_width * (col as f32 + 0.5),
+            board.y + row_height * (row as f32 + 0.5),
+        ];
+        (center, theme::mark_radius(column_width.min(row_height)))
+    }
+
+    // Adds the mark of the given player, centered in the given cell.
+    fn build_piece(
+        &self,
+        ctx: &ggez::Context,
+        mb: &mut MeshBuilder,
+        (col, row): (usize, usize),
+        player: Player,
+        color: graphics::Color,
+    ) -> ggez::GameResult {
+        let ([x, y], size) = self.piece_geometry(ctx, (col, row));
+        let stroke = self.piece_stroke(ctx);
+        self.build_mark(mb, [x, y], size, stroke, player, color)
+    }
+
+    fn piece_stroke(&self, ctx: &ggez::Context) -> f32 {
+        let board = self.board_rect(ctx);
+        let cell = board.w.min(board.h) / self.shown().size as f32;
+        theme::stroke(self.theme.piece_stroke, cell)
+    }
+
+    // Adds the mark of the given player centered on a point.
+    fn build_mark(
+        &self,
+        mb: &mut MeshBuilder,
+        [x, y]: [f32; 2],
+        size: f32,
+        stroke: f32,
+        player: Player,
+        color: graphics::Color,
+    ) -> ggez::GameResult {
+        match self.theme.shape(player) {
+            Shape::Circle => {
+                mb.circle(DrawMode::stroke(stroke), [x, y], size, 0.1, color);
+            }
+            Shape::Cross => {
+                mb.line(&[[x - size, y - size], [x + size, y + size]], stroke, color)?;
+                mb.line(&[[x + size, y - size], [x - size, y + size]], stroke, color)?;
+            }
+            Shape::Triangle => {
+                mb.polygon(
+                    DrawMode::stroke(stroke),
+                    &theme::triangle(x, y, size),
+                    color,
+                )?;
+            }
+            Shape::Square => {
+                let half = theme::square_half_side(size);
+                let square = graphics::Rect::new(x - half, y - half, half * 2.0, half * 2.0);
+                mb.rectangle(DrawMode::stroke(stroke), square, color);
+            }
+        }
+        Ok(())
+    }
+
+    // Shows the suggested move as a faint mark of the player to move.
+    fn build_hint(&self, ctx: &ggez::Context, mb: &mut MeshBuilder) -> ggez::GameResult {
+        if let Some((cell, _)) = self.hint {
+            let mut color = self.theme.player_color(self.game.turn);
+            color.a *= 0.35;
+            self.build_piece(ctx, mb, cell, self.game.turn, color)?;
+        }
+        Ok(())
+    }
+
+    // Whether the pieces are drawn from images in sprite batches.
+    fn batched(&self) -> bool {
+        self.sprites.is_some() || self.shown().grid.size() > BATCH_SIZE
+    }
+
+    // Fills a batch per player with the pieces on the board, drawn from the
+    // skin's images or from images of the theme's marks.
+    fn build_batches(
+        &mut self,
+        ctx: &mut ggez::Context,
+    ) -> ggez::GameResult<(SpriteBatch, SpriteBatch)> {
+        let (naughts, crosses, flipped) = match &self.sprites {
+            Some((naughts, crosses)) => (naughts.clone(), crosses.clone(), false),
+            None => {
+                let (naughts, crosses) = self.stamps(ctx)?;
+                (naughts, crosses, true)
+            }
+        };
+        let mut batches = (
+            SpriteBatch::new(naughts.clone()),
+            SpriteBatch::new(crosses.clone()),
+        );
+        for (cell, player) in self.shown().grid.pieces() {
+            let (batch, image) = match player {
+                Player::Naughts => (&mut batches.0, &naughts),
+                Player::Crosses => (&mut batches.1, &crosses),
+            };
+            batch.add(self.sprite_param(ctx, cell, image, flipped));
+        }
+        Ok(batches)
+    }
+
+    // Images of each player's mark at the current cell size, drawn onto
+    // canvases and reused until the size or theme changes.
+    fn stamps(
+        &mut self,
+        ctx: &mut ggez::Context,
+    ) -> ggez::GameResult<(graphics::Image, graphics::Image)> {
+        let (_, radius) = self.piece_geometry(ctx, (0, 0));
+        let key = (
+            radius,
+            self.piece_stroke(ctx),
+            [
+                self.theme.player_color(Player::Naughts),
+                self.theme.player_color(Player::Crosses),
+            ],
+            [
+                self.theme.shape(Player::Naughts),
+                self.theme.shape(Player::Crosses),
+            ],
+        );
+        match &self.stamps {
+            Some((built, images)) if *built == key => return Ok(images.clone()),
+            _ => {}
+        }
+        self.rebuilds += 1;
+        let images = (
+            self.stamp(ctx, &key, Player::Naughts)?,
+            self.stamp(ctx, &key, Player::Crosses)?,
+        );
+        self.stamps = Some((key, images.clone()));
+        Ok(images)
+    }
+
+    // Draws a player's mark onto a transparent canvas just large enough to
+    // hold it.
+    fn stamp(
+        &self,
+        ctx: &mut ggez::Context,
+        &(radius, stroke, colors, _): &StampKey,
+        player: Player,
+    ) -> ggez::GameResult<graphics::Image> {
+        let side = ((radius + stroke) * 2.0)
+            .ceil()
+            .max(1.0)
+            .min(f32::from(u16::MAX));
+        let color = match player {
+            Player::Naughts => colors[0],
+            Player::Crosses => colors[1],
+        };
+        let mut mb = MeshBuilder::new();
+        self.build_mark(
+            &mut mb,
+            [side / 2.0, side / 2.0],
+            radius,
+            stroke,
+            player,
+            color,
+        )?;
+        let mesh = mb.build(ctx)?;
+        let canvas = graphics::Canvas::new(ctx, side as u16, side as u16, NumSamples::One)?;
+        graphics::set_canvas(ctx, Some(&canvas));
+        graphics::set_screen_coordinates(ctx, graphics::Rect::new(0.0, 0.0, side, side))?;
+        graphics::clear(ctx, graphics::Color::new(0.0, 0.0, 0.0, 0.0));
+        let drawn = graphics::draw(ctx, &mesh, graphics::DrawParam::default());
+        graphics::set_canvas(ctx, None);
+        fit_screen_coordinates(ctx)?;
+        drawn?;
+        Ok(canvas.into_inner())
+    }
+
+    // Draws the hint and the pending move as faint images of the player to
+    // move, when the theme has a skin. The pieces themselves are batched.
+    fn draw_sprites(&self, ctx: &mut ggez::Context) -> ggez::GameResult {
+        if self.sprites.is_none() {
+            return Ok(());
+        }
+        if self.viewer.is_none() {
+            if let Some((cell, _)) = self.hint {
+                self.draw_sprite(ctx, cell, self.game.turn, 0.35)?;
+            }
+            if let Some(cell) = self.pending {
+                self.draw_sprite(ctx, cell, self.game.turn, 0.5)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn draw_sprite(
+        &self,
+        ctx: &mut ggez::Context,
+        cell: (usize, usize),
+        player: Player,
+        alpha: f32,
+    ) -> ggez::GameResult {
+        let image = match (&self.sprites, player) {
+            (Some((naughts, _)), Player::Naughts) => naughts,
+            (Some((_, crosses)), Player::Crosses) => crosses,
+            (None, _) => return Ok(()),
+        };
+        let param = self
+            .sprite_param(ctx, cell, image, false)
+            .color([1.0, 1.0, 1.0, alpha].into());
+        graphics::draw(ctx, image, param)
+    }
+
+    // Places an image centered in the given cell, scaled to the size of a
+    // mark. Images drawn onto canvases come out upside down, so those are
+    // flipped back.
+    fn sprite_param(
+        &self,
+        ctx: &ggez::Context,
+        cell: (usize, usize),
+        image: &graphics::Image,
+        flipped: bool,
+    ) -> graphics::DrawParam {
+        let ([x, y], radius) = self.piece_geometry(ctx, cell);
+        let (w, h) = (f32::from(image.width()), f32::from(image.height()));
+        let scale = radius * 2.0 / w.max(h).max(1.0);
+        let (top, scale_y) = if flipped {
+            (y + h * scale / 2.0, -scale)
+        } else {
+            (y - h * scale / 2.0, scale)
+        };
+        graphics::DrawParam::default()
+            .dest([x - w * scale / 2.0, top])
+            .scale([scale, scale_y])
+    }
+
+    // Shows the cell awaiting confirmation as a ghost mark of the player to
+    // move.
+    fn build_pending(&self, ctx: &ggez::Context, mb: &mut MeshBuilder) -> ggez::GameResult {
+        if let Some(cell) = self.pending {
+            let mut color = self.theme.player_color(self.game.turn);
+            color.a *= 0.5;
+            self.build_piece(ctx, mb, cell, self.game.turn, color)?;
+        }
+        Ok(())
+    }
+
+    // Labels the columns with letters along the bottom row and the rows with
+    // numbers down the left column, inside the corners of the edge cells.
+    fn draw_coordinates(&self, ctx: &mut ggez::Context) -> ggez::GameResult {
+        let game = self.shown();
+        let board = self.board_rect(ctx);
+        let cell = board.w / game.size as f32;
+        let size = (TEXT_SIZE * dpi_scale(ctx)).min(cell / 3.0);
+        // Labels too small to read would only clutter the board.
+        if size < 6.0 {
+            return Ok(());
+        }
+        let mut color: graphics::Color = self.theme.text.into();
+        color.a *= 0.6;
+        let inset = size / 4.0;
+        let mut labels = vec![];
+        for col in 0..game.size {
+            labels.push((column_name(col), col, game.size - 1, true));
+        }
+        for row in 0..game.size {
+            labels.push((game.row_name(row).to_string(), 0, row, false));
+        }
+        for (label, col, row, bottom) in labels {
+            let mut text = graphics::Text::new(label);
+            text.set_font(graphics::Font::default(), graphics::Scale::uniform(size));
+            let (x, y) = (board.x + cell * col as f32, board.y + cell * row as f32);
+            let dest = if bottom {
+                [
+                    x + cell - text.width(ctx) as f32 - inset,
+                    y + cell - text.height(ctx) as f32 - inset,
+                ]
+            } else {
+                [x + inset, y + inset]
+            };
+            let param = graphics::DrawParam::default().dest(dest).color(color);
+            graphics::draw(ctx, &text, param)?;
+        }
+        Ok(())
+    }
+
+    // Shows frame rate, frame time and mesh rebuilds below the score, and the
+    // live round's statistics below them.
+    fn draw_debug(&self, ctx: &mut ggez::Context) -> ggez::GameResult {
+        let frame = timer::duration_to_f64(timer::average_delta(ctx)) * 1000.0;
+        let (snapshot, snapshots) = self.snapshots.position();
+        let stats = format!(
+            "FPS {:.1}  frame {:.2} ms  mesh rebuilds {}  snapshot {} of {}\n{}",
+            timer::fps(ctx),
+            frame,
+            self.rebuilds,
+            snapshot,
+            snapshots,
+            self.stats.summary()
+        );
+        let text = overlay_text(stats, self.hud_scale(ctx));
+        let (hud, margin) = (
+            HUD_HEIGHT * self.hud_scale(ctx),
+            MARGIN * self.hud_scale(ctx),
+        );
+        let param = graphics::DrawParam::default()
+            .dest([margin, hud + margin])
+            .color(self.theme.text.into());
+        graphics::draw(ctx, &text, param)
+    }
+
+    // Which openings are being practised, and how accurately so far.
+    fn trainer_status(&self) -> String {
+        match &self.trainer {
+            Some(trainer) => {
+                let mut progress = self.settings.trainer.clone();
+                format!(
+                    "Trainer: {} openings, {}",
+                    trainer.opening.name(),
+                    progress.accuracy(trainer.opening).summary()
+                )
+            }
+            None => String::new(),
+        }
+    }
+
+    // What the puzzle on the board asks for, and how the attempt is going.
+    fn puzzle_status(&self) -> String {
+        let puzzle = match &self.puzzle {
+            Some(puzzle) => puzzle,
+            None => return String::new(),
+        };
+        let status = if self.game.victor() == Some(puzzle.solver()) {
+            "solved"
+        } else if self.puzzle_failed {
+            "failed"
+        } else {
+            "to play"
+        };
+        format!(
+            "Puzzle: {} wins in {} ({})",
+            puzzle.solver().symbol(),
+            puzzle.moves,
+            status
+        )
+    }
+
+    // Draws the evaluation bar down the left edge of the window, filled from
+    // the bottom in naughts' color as naughts' position improves, and the
+    // best moves with their continuations in the top right corner.
+    fn draw_analysis(&self, ctx: &mut ggez::Context) -> ggez::GameResult {
+        let report = match self.analyst.as_ref().and_then(Analyst::latest) {
+            Some(report) => report,
+            None => return Ok(()),
+        };
+        let margin = MARGIN * self.hud_scale(ctx);
+        let fitted = self.fitted_rect(ctx);
+        let naughts = fitted.h * analysis::bar_fraction(report.score);
+        let mesh = MeshBuilder::new()
+            .rectangle(
+                DrawMode::fill(),
+                graphics::Rect::new(margin, fitted.y, margin, fitted.h - naughts),
+                self.theme.player_color(Player::Crosses),
+            )
+            .rectangle(
+                DrawMode::fill(),
+                graphics::Rect::new(margin, fitted.y + fitted.h - naughts, margin, naughts),
+                self.theme.player_color(Player::Naughts),
+            )
+            .build(ctx)?;
+        graphics::draw(ctx, &mesh, graphics::DrawParam::default())?;
+        let game = self.shown();
+        let mut summary = format!(
+            "Depth {}  {}",
+            report.depth,
+            review::format_score(report.score)
+        );
+        for line in &report.lines {
+            summary.push_str(&format!(
+                "\n{} ({})",
+                game.cell_name(line.cell),
+                review::format_score(line.score)
+            ));
+            for cell in &line.continuation {
+                summary.push(' ');
+                summary.push_str(&game.cell_name(*cell));
+            }
+        }
+        let text = overlay_text(summary, self.hud_scale(ctx));
+        let (w, _) = graphics::drawable_size(ctx);
+        let dest = [
+            w - text.width(ctx) as f32 - margin * 2.0,
+            HUD_HEIGHT * self.hud_scale(ctx) + margin,
+        ];
+        let param = graphics::DrawParam::default()
+            .dest(dest)
+            .color(self.theme.text.into());
+        graphics::draw(ctx, &text, param)
+    }
+
+    fn draw_score(&self, ctx: &mut ggez::Context) -> ggez::GameResult {
+        let summary = match &self.viewer {
+            Some(viewer) => format!(
+                "Replay: move {} of {}{}{}",
+                viewer.position,
+                viewer.len(),
+                if viewer.autoplay { " (playing)" } else { "" },
+                viewer
+                    .comment()
+                    .map(|comment| format!("  {}", comment))
+                    .unwrap_or_default()
+            ),
+            None => match self.countdown() {
+                _ if self.puzzle.is_some() => self.puzzle_status(),
+                _ if self.trainer.is_some() => self.trainer_status(),
+                Some(left) => format!(
+                    "{}  Next round in {}",
+                    self.score.summary(),
+                    left.as_secs() + u64::from(left.subsec_nanos() > 0)
+                ),
+                None => self.score.summary(),
+            },
+        };
+        let text = overlay_text(summary, self.hud_scale(ctx));
+        let margin = MARGIN * self.hud_scale(ctx);
+        let param = graphics::DrawParam::default()
+            .dest([margin, margin])
+            .color(self.theme.text.into());
+        graphics::draw(ctx, &text, param)
+    }
+
+    fn draw_notice(&self, ctx: &mut ggez::Context) -> ggez::GameResult {
+        if let Some(notice) = &self.notice {
+            let (w, _) = graphics::drawable_size(ctx);
+            let margin = MARGIN * self.hud_scale(ctx);
+            let text = overlay_text(notice.message.clone(), self.hud_scale(ctx));
+            let (text_w, text_h) = (text.width(ctx) as f32, text.height(ctx) as f32);
+            let dest = [w - text_w - margin * 2.0, margin];
+            let color = if notice.error {
+                let toast = graphics::Rect::new(
+                    dest[0] - margin / 2.0,
+                    dest[1] - margin / 2.0,
+                    text_w + margin,
+                    text_h + margin,
+                );
+                let mesh = MeshBuilder::new()
+                    .rectangle(DrawMode::fill(), toast, ERROR_COLOR.into())
+                    .build(ctx)?;
+                graphics::draw(ctx, &mesh, graphics::DrawParam::default())?;
+                graphics::WHITE
+            } else {
+                self.theme.text.into()
+            };
+            let param = graphics::DrawParam::default().dest(dest).color(color);
+            graphics::draw(ctx, &text, param)?;
+        }
+        Ok(())
+    }
+
+    // Draws each player's symbol and name below the board, marking whose turn
+    // it is while the round is in progress.
+    fn draw_names(&self, ctx: &mut ggez::Context) -> ggez::GameResult {
+        let game = self.shown();
+        let (w, h) = graphics::drawable_size(ctx);
+        let (hud, margin) = (
+            HUD_HEIGHT * self.hud_scale(ctx),
+            MARGIN * self.hud_scale(ctx),
+        );
+        let in_progress = game.in_progress();
+        for player in &[Player::Naughts, Player::Crosses] {
+            let mut label = format!("{} {}", player.symbol(), self.name(*player));
+            match &self.clock {
+                Some(clock) if self.viewer.is_none() => {
+                    label.push_str(&format!(" {}", clock::format(clock.remaining(*player))));
+                }
+                _ => {}
+            }
+            if in_progress && game.turn == *player {
+                label.push_str(" (to move)");
+            } else if game.timed_out == Some(*player) {
+                label.push_str(" (out of time)");
+            }
+            let text = overlay_text(label, self.hud_scale(ctx));
+            let x = match player {
+                Player::Naughts => margin,
+                Player::Crosses => w - text.width(ctx) as f32 - margin,
+            };
+            let param = graphics::DrawParam::default()
+                .dest([x, h - hud + margin])
+                .color(self.theme.player_color(*player));
+            graphics::draw(ctx, &text, param)?;
+        }
+        Ok(())
+    }
+
+    // Draws the time left for the player to move as a bar along the bottom of
+    // the window, pulsing red when time is nearly up.
+    fn build_clock(&self, ctx: &ggez::Context, mb: &mut MeshBuilder) -> ggez::GameResult {
+        let clock = match &self.clock {
+            Some(clock) => clock,
+            None => return Ok(()),
+        };
+        let player = match clock.running() {
+            Some(player) => player,
+            None => return Ok(()),
+        };
+        let (w, h) = graphics::drawable_size(ctx);
+        let remaining = clock.remaining(player);
+        // Increments can fill a bank past its starting time.
+        let fraction = (timer::duration_to_f64(remaining)
+            / timer::duration_to_f64(clock.control.limit))
+        .min(1.0);
+        let height = self.theme.grid_stroke * 2.0 * dpi_scale(ctx);
+        let bar = graphics::Rect::new(0.0, h - height, w * fraction as f32, height);
+        let color = if remaining < clock::LOW_TIME {
+            let pulse = (timer::duration_to_f64(timer::time_since_start(ctx)) * 6.0).sin();
+            [1.0, 0.0, 0.0, 0.75 + 0.25 * pulse as f32].into()
+        } else {
+            self.theme.player_color(player)
+        };
+        mb.rectangle(DrawMode::fill(), bar, color);
+        Ok(())
+    }
+
+    fn build_cursor(&self, ctx: &ggez::Context, mb: &mut MeshBuilder) -> ggez::GameResult {
+        if let Some((col, row)) = self.cursor {
+            let board = self.board_rect(ctx);
+            let column_width = board.w / self.game.size as f32;
+            let row_height = board.h / self.game.size as f32;
+            let cell = graphics::Rect::new(
+                board.x + column_width * col as f32,
+                board.y + row_height * row as f32,
+                column_width,
+                row_height,
+            );
+            let stroke = theme::stroke(self.theme.grid_stroke * 2.0, column_width);
+            mb.rectangle(
+                DrawMode::stroke(stroke),
+                cell,
+                self.theme.player_color(self.game.turn),
+            );
+        }
+        Ok(())
+    }
+
+    // Shows how far through the replay the viewer is as a bar along the top of
+    // the board.
+    fn build_scrubber(&self, ctx: &ggez::Context, mb: &mut MeshBuilder) -> ggez::GameResult {
+        if let Some(viewer) = &self.viewer {
+            let board = self.board_rect(ctx);
+            let height = self.theme.grid_stroke * 2.0 * dpi_scale(ctx);
+            let progress = if !viewer.is_empty() {
+                viewer.position as f32 / viewer.len() as f32
+            } else {
+                1.0
+            };
+            let color = self.theme.text.into();
+            let track = graphics::Rect::new(board.x, board.y - height * 2.0, board.w, height);
+            mb.rectangle(DrawMode::stroke(1.0), track, color);
+            let bar = graphics::Rect::new(track.x, track.y, track.w * progress, track.h);
+            mb.rectangle(DrawMode::fill(), bar, color);
+        }
+        Ok(())
+    }
+
+    fn build_throughline(&self, ctx: &ggez::Context, mb: &mut MeshBuilder) -> ggez::GameResult {
+        let game = self.shown();
+        if let Some((_, Axis(start, end))) = &game.winner {
+            let board = self.board_rect(ctx);
+            let column_size = board.w / game.size as f32;
+            let row_size = board.h / game.size as f32;
+            let stroke = theme::stroke(self.theme.win_line_stroke, column_size);
+            let coords = [
+                [
+                    board.x + start.0 as f32 * column_size + column_size / 2.0 - stroke / 2.0,
+                    board.y + start.1 as f32 * row_size + row_size / 2.0 - stroke / 2.0,
+                ],
+                [
+                    board.x + end.0 as f32 * column_size + column_size / 2.0 - stroke / 2.0,
+                    board.y + end.1 as f32 * row_size + row_size / 2.0 - stroke / 2.0,
+                ],
+            ];
+            mb.line(&coords, stroke, self.theme.win_line.into())?;
+        }
+        Ok(())
+    }
+
+    // Places a piece for the current player, recording the result if the move
+    // ends the round.
+    fn place(&mut self, col: usize, row: usize) {
+        self.branch();
+        let player = self.game.turn;
+        let cell = match self.game.place(col, row) {
+            Some(cell) => cell,
+            None => {
+                self.refuse(col, row);
+                return;
+            }
+        };
+        self.hint = None;
+        self.hinter = None;
+        self.pending = None;
+        self.stats.moved(player);
+        if let Some(clock) = &mut self.clock {
+            clock.moved(player);
+        }
+        // Puzzles and quizzes are practice, so they leave the score alone.
+        if !self.game.in_progress() && !self.practising() {
+            self.score.record(self.game.victor());
+            self.stats.finish();
+            self.notify(self.stats.summary());
+        }
+        self.update_clock();
+        self.snapshots.record(&self.game);
+        self.narrate(format!(
+            "{} plays {}.",
+            self.name(player),
+            self.game.cell_name(cell)
+        ));
+        self.changed();
+        self.judge_puzzle(player);
+        self.judge_answer(cell);
+    }
+
+    // Plays on from the snapshot being inspected in debug mode, if any,
+    // dropping the states that followed it.
+    fn branch(&mut self) {
+        if let Some(game) = self.snapshots.branch() {
+            self.game = game;
+            self.pending = None;
+            self.update_clock();
+            self.changed();
+        }
+    }
+
+    // Places a piece at a cell chosen with the mouse, keyboard or gamepad.
+    // When moves need confirming, the first choice only marks the cell the
+    // piece would land in, and choosing it again places the piece.
+    fn choose(&mut self, col: usize, row: usize) {
+        self.branch();
+        if !self.settings.confirm_moves {
+            self.place(col, row);
+            return;
+        }
+        let landing = self.game.landing(col, row);
+        if landing.is_none() {
+            self.pending = None;
+            self.refuse(col, row);
+        } else if landing == self.pending {
+            self.place(col, row);
+        } else {
+            self.pending = landing;
+            self.dirty = true;
+        }
+    }
+
+    // Explains why a piece cannot be placed at the given cell.
+    fn refuse(&mut self, col: usize, row: usize) {
+        let reason = if self.game.phase() == Phase::Finished {
+            "The round is over".to_owned()
+        } else if self.game.gravity {
+            format!("Column {} is full", column_name(col))
+        } else {
+            format!("{} is taken", self.game.cell_name((col, row)))
+        };
+        self.warn(reason);
+    }
+
+    // Takes back the last move, removing the result from the score if that
+    // move ended the round.
+    fn undo(&mut self) {
+        if self.practising() {
+            self.warn("Undo is off in practice. Restart to try again.".to_owned());
+            return;
+        }
+        self.branch();
+        let ended = !self.game.in_progress();
+        let victor = self.game.victor();
+        let cell = match self.game.undo() {
+            Some(cell) => cell,
+            None => {
+                self.warn("Nothing to undo".to_owned());
+                return;
+            }
+        };
+        self.hint = None;
+        self.hinter = None;
+        self.pending = None;
+        if ended {
+            self.score.unrecord(victor);
+        }
+        self.stats.undone();
+        if let Some(clock) = &mut self.clock {
+            clock.undone();
+        }
+        self.update_clock();
+        self.snapshots.record(&self.game);
+        self.narrate(format!(
+            "{} takes back {}.",
+            self.name(self.game.turn),
+            self.game.cell_name(cell)
+        ));
+        self.changed();
+    }
+
+    // Whether a puzzle or quiz is on the board rather than a game.
+    fn practising(&self) -> bool {
+        self.puzzle.is_some() || self.trainer.is_some()
+    }
+
+    // Sets a puzzle on the board, keeping the local game to return to.
+    fn start_puzzle(&mut self, puzzle: Puzzle) {
+        if self.local.is_none() {
+            self.local = Some(self.game.clone());
+        }
+        self.trainer = None;
+        let game = puzzle.game();
+        self.notify(format!(
+            "{} to move and win in {}",
+            self.name(puzzle.solver()),
+            puzzle.moves
+        ));
+        self.puzzle = Some(puzzle);
+        self.puzzle_failed = false;
+        self.resume(game);
+    }
+
+    // Sets the first question of an opening quiz on the board, keeping the
+    // local game to return to.
+    fn start_trainer(&mut self, opening: Opening) {
+        if self.local.is_none() {
+            self.local = Some(self.game.clone());
+        }
+        self.puzzle = None;
+        let trainer = Trainer::new(opening, timestamp());
+        let game = trainer.question.clone();
+        self.trainer = Some(trainer);
+        self.resume(game);
+        self.ask();
+    }
+
+    // Announces the question on the board.
+    fn ask(&mut self) {
+        let turn = self.game.turn;
+        self.notify(format!("Find the best move for {}", self.name(turn)));
+    }
+
+    // Judges the first move played in the quiz on the board, if any, keeping
+    // count of correct answers in the settings.
+    fn judge_answer(&mut self, cell: (usize, usize)) {
+        let trainer = match &mut self.trainer {
+            Some(trainer) => trainer,
+            None => return,
+        };
+        let correct = match trainer.answer(cell) {
+            Some(correct) => correct,
+            None => return,
+        };
+        let best: Vec<String> = trainer
+            .best
+            .iter()
+            .map(|cell| trainer.question.cell_name(*cell))
+            .collect();
+        self.settings
+            .trainer
+            .accuracy(trainer.opening)
+            .record(correct);
+        if correct {
+            self.notify("Correct! Restart for the next opening.".to_owned());
+        } else {
+            self.warn(format!("Not the best. Try {}.", best.join(" or ")));
+        }
+    }
+
+    // Forgets the puzzle or quiz on the board and the local game set aside
+    // for it, before another game replaces it.
+    fn leave_puzzle(&mut self) {
+        self.puzzle = None;
+        self.trainer = None;
+        self.local = None;
+    }
+
+    // Judges the solver's move in the puzzle on the board, if any, playing the
+    // opponent's reply while the win is still forced.
+    fn judge_puzzle(&mut self, player: Player) {
+        let verdict = match &self.puzzle {
+            Some(puzzle) if !self.puzzle_failed && player == puzzle.solver() => {
+                puzzle.judge(&self.game)
+            }
+            _ => return,
+        };
+        match verdict {
+            Verdict::Solved => self.notify("Puzzle solved!".to_owned()),
+            Verdict::Reply((col, row)) => self.place(col, row),
+            Verdict::Failed => {
+                self.puzzle_failed = true;
+                self.warn("That lets the win slip. Restart to try again.".to_owned());
+            }
+        }
+    }
+
+    // Starts looking for a move to suggest, which is shown once it is found.
+    fn show_hint(&mut self) {
+        self.hinter = Some(Hinter::new(&self.game));
+    }
+
+    // Shows the hint once its search has finished.
+    fn check_hint(&mut self) {
+        let hinter = match &self.hinter {
+            Some(hinter) => hinter,
+            None => return,
+        };
+        // The game moved on before the hint was found.
+        if !hinter.is_for(&self.game) {
+            self.hinter = None;
+            return;
+        }
+        let hint = match hinter.poll() {
+            Some(hint) => hint,
+            None => return,
+        };
+        self.hinter = None;
+        match hint {
+            Some(cell) => {
+                self.hint = Some((cell, Instant::now()));
+                self.notify(format!("Try {}", self.game.cell_name(cell)));
+            }
+            None => self.warn("No moves to suggest".to_owned()),
+        }
+    }
+
+    // Runs the clock of the player to move. The clocks only start once the
+    // first move has been made, and stop when the round ends.
+    fn update_clock(&mut self) {
+        if let Some(clock) = &mut self.clock {
+            match self.game.phase() {
+                Phase::Playing => clock.run(self.game.turn),
+                Phase::Lobby | Phase::Finished => clock.stop(),
+            }
+        }
+    }
+
+    // Time left before the next round starts on its own, while counting down.
+    fn countdown(&self) -> Option<Duration> {
+        let (delay, ended) = (self.next_round?, self.round_over?);
+        Some(delay.checked_sub(ended.elapsed()).unwrap_or_default())
+    }
+
+    // Starts the next round once the countdown after a round ends runs out.
+    // The countdown waits while a replay or the menu is open.
+    fn check_next_round(&mut self) {
+        if self.next_round.is_none() || self.practising() || self.game.phase() != Phase::Finished {
+            self.round_over = None;
+            return;
+        }
+        if self.viewer.is_some() || self.menu.is_some() {
+            return;
+        }
+        match self.countdown() {
+            None => self.round_over = Some(Instant::now()),
+            Some(left) if left == Duration::from_secs(0) => self.restart(),
+            Some(_) => {}
+        }
+    }
+
+    // Starts analysing the position on the board whenever it changes while
+    // analysis is on, and redraws as the analysis deepens.
+    fn check_analysis(&mut self) {
+        if !self.analysing || self.menu.is_some() {
+            return;
+        }
+        let shown = self.shown();
+        if !self
+            .analyst
+            .as_ref()
+            .map_or(false, |analyst| analyst.analyses(shown))
+        {
+            let shown = shown.clone();
+            self.analyst
+                .get_or_insert_with(Analyst::new)
+                .analyse(&shown);
+        }
+        if self.analyst.as_mut().map_or(false, Analyst::poll) {
+            self.dirty = true;
+        }
+    }
+
+    // Starts the computer's review of the replay being viewed, or of the live
+    // game once its round is over.
+    fn start_review(&mut self) {
+        let replay = match &self.viewer {
+            Some(viewer) => viewer.replay.clone(),
+            None if self.game.phase() == Phase::Finished => Replay::from_game(&self.game),
+            None => {
+                self.warn("Finish the round to review it.".to_owned());
+                return;
+            }
+        };
+        self.reviewer = Some(Reviewer::new(replay));
+        self.notify("Reviewing the game...".to_owned());
+    }
+
+    // Opens a finished review in the replay viewer at the first mistake.
+    fn check_review(&mut self) {
+        let review = match self.reviewer.as_ref().and_then(Reviewer::poll) {
+            Some(review) => review,
+            None => return,
+        };
+        self.reviewer = None;
+        self.notify(format!("Review: {}", review.summary()));
+        let mut viewer = Viewer::new(review.replay.clone(), self.replay_delay);
+        viewer.review = Some(review);
+        viewer.next_mistake();
+        self.viewer = Some(viewer);
+        self.changed();
     }
-}
 
-struct Axis((usize, usize), (usize, usize));
+    // Opens a replay or a game record dropped onto the window in the replay
+    // viewer, in place of the current game.
+    fn open_dropped(&mut self, path: &Path) {
+        let opened = if path.extension().is_some_and(|ext| ext == "txt") {
+            std::fs::read_to_string(path)
+                .map_err(|err| err.to_string())
+                .and_then(|text| Record::parse(&text))
+                .map(|record| (record.game(), record.replay, Some(record.names)))
+        } else {
+            Replay::read(path)
+                .map(|replay| (replay.end(), replay, None))
+                .map_err(|err| err.to_string())
+        };
+        let (game, replay, names) = match opened {
+            Ok(opened) => opened,
+            Err(err) => {
+                self.warn(format!("Opening {} failed: {}", path.display(), err));
+                return;
+            }
+        };
+        if let Some(names) = names {
+            self.names = names;
+        }
+        self.leave_puzzle();
+        self.resume(game);
+        self.viewer = Some(Viewer::new(replay, self.replay_delay));
+        self.menu = None;
+        self.notify(format!("Opened {}", path.display()));
+        self.changed();
+    }
 
-struct MainState {
-    winner: Option<(Player, Axis)>,
-    turn: Player,
-    grid: Vec<Vec<Option<Player>>>,
-    size: usize,
-    win: usize,
-    gravity: bool,
-}
+    // Ends the round if the player to move has run out of time.
+    fn check_clock(&mut self) {
+        let flagged = match self.clock.as_ref().and_then(Clock::flagged) {
+            Some(flagged) => flagged,
+            None => return,
+        };
+        self.game.time_out(flagged);
+        if !self.practising() {
+            self.score.record(self.game.victor());
+            self.stats.finish();
+            self.notify(self.stats.summary());
+        }
+        self.update_clock();
+        self.narrate(format!("{} is out of time.", self.name(flagged)));
+        self.changed();
+    }
 
-impl MainState {
-    fn new(size: usize, win: usize, gravity: bool) -> ggez::GameResult<MainState> {
-        let s = MainState {
-            winner: None,
-            turn: Player::Naughts,
-            grid: vec![vec![None; size]; size],
-            size: size,
-            win: win,
-            gravity: gravity,
+    // Moves the keyboard cursor, showing it in the middle of the board if it
+    // was hidden.
+    fn move_cursor(&mut self, dx: i32, dy: i32) {
+        let max = self.game.size as i32 - 1;
+        self.cursor = match self.cursor {
+            Some((col, row)) => Some((
+                (col as i32 + dx).max(0).min(max) as usize,
+                (row as i32 + dy).max(0).min(max) as usize,
+            )),
+            None => Some((self.game.size / 2, self.game.size / 2)),
         };
-        Ok(s)
+        self.dirty = true;
     }
 
-    fn build_grid(&self, ctx: &ggez::Context, mb: &mut MeshBuilder) -> ggez::GameResult {
-        let ((w, h), stroke, color) = (graphics::drawable_size(ctx), 2.0, self.turn.color());
-        let column_width = w / self.size as f32;
-        for ii in 1..self.size {
-            let offset = column_width * ii as f32;
-            mb.line(&[[offset, 0.0], [offset, h]], stroke, color)?;
+    // Handles a gamepad button press from any connected gamepad.
+    fn gamepad_button(&mut self, ctx: &mut Context, btn: Button) {
+        if self.menu.is_some() {
+            match btn {
+                Button::DPadUp => self.menu_step(-1),
+                Button::DPadDown => self.menu_step(1),
+                Button::South => {
+                    if let Some(action) = self.menu_action() {
+                        self.activate(ctx, action);
+                    }
+                }
+                Button::East => self.activate(ctx, Action::Back),
+                _ => {}
+            }
+            return;
         }
-        let row_height = h / self.size as f32;
-        for ii in 1..self.size {
-            let offset = row_height * ii as f32;
-            mb.line(&[[0.0, offset], [w, offset]], stroke, color)?;
+        if self.viewer.is_some() {
+            return;
+        }
+        match btn {
+            Button::DPadLeft => self.move_cursor(-1, 0),
+            Button::DPadRight => self.move_cursor(1, 0),
+            Button::DPadUp => self.move_cursor(0, -1),
+            Button::DPadDown => self.move_cursor(0, 1),
+            Button::South => match self.cursor {
+                Some((col, row)) => self.choose(col, row),
+                None => self.move_cursor(0, 0),
+            },
+            Button::Start => self.restart(),
+            _ => {}
         }
-        Ok(())
     }
 
-    fn build_players(&self, ctx: &ggez::Context, mb: &mut MeshBuilder) -> ggez::GameResult {
-        let (w, h) = graphics::drawable_size(ctx);
-        let column_width = w / self.size as f32;
-        let row_height = h / self.size as f32;
-        let size = (column_width + row_height) / 2.0 / 4.0;
-        for (ii, col) in self.grid.iter().enumerate() {
-            for (jj, cell) in col.iter().enumerate() {
-                if let Some(player) = cell {
-                    let (x, y) = (
-                        (column_width) * ((ii + 1) as f32) - (column_width / 2.0),
-                        (row_height) * ((jj + 1) as f32) - (row_height / 2.0),
-                    );
-                    let color = player.color();
-                    match player {
-                        Player::Naughts => {
-                            mb.circle(DrawMode::stroke(2.0), [x, y], size, 0.1, color);
-                        }
-                        Player::Crosses => {
-                            mb.line(&[[x - size, y - size], [x + size, y + size]], 2.0, color)?;
-                            mb.line(&[[x + size, y - size], [x - size, y + size]], 2.0, color)?;
-                        }
-                    }
+    // Moves the cursor, or steps through the menu, once each time a stick is
+    // pushed past halfway.
+    fn gamepad_axis(&mut self, axis: event::Axis, value: f32) {
+        let direction = if value > 0.5 {
+            1
+        } else if value < -0.5 {
+            -1
+        } else {
+            0
+        };
+        match axis {
+            event::Axis::LeftStickX if direction != self.stick.0 => {
+                self.stick.0 = direction;
+                if direction != 0 && self.menu.is_none() {
+                    self.move_cursor(direction, 0);
                 }
             }
+            // The stick's y axis points up, whereas rows count downwards.
+            event::Axis::LeftStickY if direction != self.stick.1 => {
+                self.stick.1 = direction;
+                if direction != 0 && self.menu.is_some() {
+                    self.menu_step(-direction);
+                } else if direction != 0 {
+                    self.move_cursor(0, -direction);
+                }
+            }
+            _ => {}
         }
-        Ok(())
     }
+}
 
-    fn build_throughline(&self, ctx: &ggez::Context, mb: &mut MeshBuilder) -> ggez::GameResult {
-        if let Some((_, Axis(start, end))) = &self.winner {
-            let (w, h) = graphics::drawable_size(ctx);
-            let stroke = 2.0;
-            let column_size = w / self.size as f32;
-            let row_size = h / self.size as f32;
-            let coords = [
-                [
-                    start.0 as f32 * column_size + column_size / 2.0 - stroke / 2.0,
-                    start.1 as f32 * row_size + row_size / 2.0 - stroke / 2.0,
-                ],
-                [
-                    end.0 as f32 * column_size + column_size / 2.0 - stroke / 2.0,
-                    end.1 as f32 * row_size + row_size / 2.0 - stroke / 2.0,
-                ],
-            ];
-            mb.line(&coords, stroke, [1.0, 1.0, 1.0, 1.0].into())?;
+impl event::EventHandler for MainState {
+    fn update(&mut self, ctx: &mut ggez::Context) -> ggez::GameResult {
+        if let Err(err) = self.autosave(ctx) {
+            self.warn(format!("Autosave failed: {}", err));
+        }
+        if self.viewer.as_mut().map_or(false, Viewer::tick) {
+            self.changed();
+        }
+        self.check_clock();
+        self.check_next_round();
+        self.check_analysis();
+        self.check_review();
+        self.check_hint();
+        if let Some((_, shown)) = self.hint {
+            if shown.elapsed() >= HINT_DURATION {
+                self.hint = None;
+                self.dirty = true;
+            }
+        }
+        if self.debug
+            || self.clock.as_ref().and_then(Clock::running).is_some()
+            || self.countdown().is_some()
+        {
+            // Keep the countdowns and the frame statistics moving.
+            self.dirty = true;
+        }
+        if let Some(notice) = &self.notice {
+            if notice.shown.elapsed() >= NOTICE_DURATION {
+                self.notice = None;
+                self.dirty = true;
+            }
+        }
+        if self.dirty {
+            // Hold the next frame back until the frame rate limit allows it.
+            let wait = self
+                .frame_time
+                .and_then(|frame_time| frame_time.checked_sub(self.last_frame.elapsed()));
+            match wait {
+                Some(wait) => timer::sleep(wait),
+                None => timer::yield_now(),
+            }
+        } else {
+            timer::sleep(IDLE_SLEEP);
         }
         Ok(())
     }
 
-    // Checks for consecutive pieces owned by this player in a given direction,
-    // returning the count of pieces.
-    fn check_direction(&self, col: i32, row: i32, x: i32, y: i32, player: Player) -> usize {
-        let mut count = 0;
-        let mut col = col;
-        let mut row = row;
-        loop {
-            col += x;
-            row += y;
-            if self.size - 1 < col as usize || col < 0 || self.size - 1 < row as usize || row < 0 {
-                return count;
-            }
-            if self.grid[col as usize][row as usize] == Some(player) {
-                count += 1;
-            } else {
-                return count;
+    fn key_up_event(&mut self, ctx: &mut Context, code: KeyCode, keymods: KeyMods) {
+        let command = self.keymap.command(code, keymods);
+        match command {
+            Some(Command::Screenshot) => {
+                match self.screenshot(ctx) {
+                    Ok(path) => self.notify(format!("Saved {}", path)),
+                    Err(err) => self.warn(format!("Screenshot failed: {}", err)),
+                }
+                return;
+            }
+            Some(Command::Debug) => {
+                self.debug = !self.debug;
+                self.snapshots.resume();
+                self.changed();
+                return;
+            }
+            Some(Command::Analysis) => {
+                self.analysing = !self.analysing;
+                self.analyst = None;
+                self.dirty = true;
+                return;
+            }
+            _ => {}
+        }
+        if self.menu_key(ctx, command) {
+            return;
+        }
+        if let Some(Command::ExportSvg) | Some(Command::ExportGif) | Some(Command::ExportRecord) =
+            command
+        {
+            let exported = match command {
+                Some(Command::ExportSvg) => self.export_svg(ctx),
+                Some(Command::ExportGif) => self.export_gif(ctx),
+                _ => self.export_record(ctx),
+            };
+            match exported {
+                Ok(path) => self.notify(format!("Exported {}", path)),
+                Err(err) => self.warn(format!("Export failed: {}", err)),
+            }
+            return;
+        }
+        if command == Some(Command::Review) {
+            self.start_review();
+            return;
+        }
+        if self.viewer.is_some() {
+            match command {
+                Some(Command::Viewer) | Some(Command::Back) => self.toggle_viewer(),
+                Some(Command::ToggleMode) => self.toggle_mode(ctx),
+                _ => {}
+            }
+            return;
+        }
+        let command = match command {
+            Some(command) => command,
+            None => return,
+        };
+        match command {
+            Command::Place | Command::Restart => match (self.cursor, self.game.phase()) {
+                (Some((col, row)), Phase::Lobby | Phase::Playing) => self.choose(col, row),
+                _ if command == Command::Restart => self.restart(),
+                _ => {}
+            },
+            Command::ToggleMode => {
+                self.toggle_mode(ctx);
+            }
+            Command::Undo => {
+                self.undo();
             }
+            Command::Viewer => {
+                self.toggle_viewer();
+            }
+            Command::Hint => {
+                self.show_hint();
+            }
+            Command::ResetView => {
+                self.reset_camera();
+            }
+            Command::Coordinates => {
+                self.settings.coordinates = !self.settings.coordinates;
+                self.dirty = true;
+                self.save_settings(ctx);
+            }
+            Command::Back if self.pending.is_some() => {
+                self.pending = None;
+                self.dirty = true;
+            }
+            Command::Back => {
+                self.open_menu();
+            }
+            Command::Save => match self.save_game(ctx) {
+                Ok(()) => self.notify("Game saved".to_owned()),
+                Err(err) => self.warn(format!("Saving failed: {}", err)),
+            },
+            Command::Load => match self.load_game(ctx) {
+                Ok(()) => self.notify("Game loaded".to_owned()),
+                Err(err) => self.warn(format!("Loading failed: {}", err)),
+            },
+            Command::Screenshot
+            | Command::Debug
+            | Command::Analysis
+            | Command::Review
+            | Command::ExportSvg
+            | Command::ExportGif
+            | Command::ExportRecord
+            | Command::Up
+            | Command::Down
+            | Command::Left
+            | Command::Right
+            | Command::ReplayStart
+            | Command::ReplayEnd
+            | Command::Autoplay
+            | Command::NextMistake
+            | Command::SnapshotBack
+            | Command::SnapshotForward => {}
         }
     }
-}
 
-impl event::EventHandler for MainState {
-    fn update(&mut self, _ctx: &mut ggez::Context) -> ggez::GameResult {
-        timer::yield_now();
-        Ok(())
+    fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) {
+        if let Err(err) = fit_screen_coordinates(ctx) {
+            self.warn(format!("Resizing failed: {}", err));
+        }
+        self.settings.window_size = Some((width, height));
+        self.clamp_pan(ctx);
+        self.changed();
+    }
+
+    fn quit_event(&mut self, ctx: &mut Context) -> bool {
+        self.save_session(ctx);
+        false
     }
 
-    fn key_up_event(&mut self, _ctx: &mut Context, code: KeyCode, _keymods: KeyMods) {
-        match code {
-            KeyCode::Return => {
-                *self = MainState::new(self.size, self.win, self.gravity).unwrap();
+    fn focus_event(&mut self, _ctx: &mut Context, _gained: bool) {
+        // The window may have been covered while unfocused.
+        self.dirty = true;
+    }
+
+    fn key_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        code: KeyCode,
+        keymods: KeyMods,
+        _repeat: bool,
+    ) {
+        // Movement happens as keys go down, so that held keys repeat.
+        let command = self.keymap.command(code, keymods);
+        if self.menu.is_some() || self.viewer_key(command) {
+            return;
+        }
+        match command {
+            Some(Command::SnapshotBack) if self.debug => {
+                self.snapshots.back();
+                self.changed();
             }
+            Some(Command::SnapshotForward) if self.debug => {
+                self.snapshots.forward();
+                self.changed();
+            }
+            Some(Command::Left) => self.move_cursor(-1, 0),
+            Some(Command::Right) => self.move_cursor(1, 0),
+            Some(Command::Up) => self.move_cursor(0, -1),
+            Some(Command::Down) => self.move_cursor(0, 1),
             _ => {}
         }
     }
 
-    fn mouse_button_up_event(&mut self, ctx: &mut Context, _btn: MouseButton, x: f32, y: f32) {
-        if self.winner.is_some() {
+    fn mouse_wheel_event(&mut self, ctx: &mut Context, _x: f32, y: f32) {
+        if self.menu.is_some() || y == 0.0 {
             return;
         }
-        let (w, h) = graphics::drawable_size(ctx);
-        let col = (x / w * self.size as f32) as usize;
-        let mut row = (y / h * self.size as f32) as usize;
-        if self.gravity {
-            // If gravity is on, we place in the first open cell starting from
-            // the last row.
-            // If the column is completely full, then the click is a non-move.
-            if self.grid[col][0].is_some() {
-                return;
-            }
-            for ii in (0..self.grid[col].len()).rev() {
-                if self.grid[col][ii].is_none() {
-                    self.grid[col][ii] = Some(self.turn);
-                    row = ii; // Capture the real row value.
-                    break;
+        let position = ggez::input::mouse::position(ctx);
+        let anchor = (position.x * dpi_scale(ctx), position.y * dpi_scale(ctx));
+        self.zoom_at(ctx, ZOOM_STEP.powf(y), anchor);
+    }
+
+    fn mouse_button_down_event(&mut self, _ctx: &mut Context, btn: MouseButton, _x: f32, _y: f32) {
+        if btn == MouseButton::Middle && self.menu.is_none() {
+            self.panning = true;
+        }
+    }
+
+    fn mouse_motion_event(&mut self, ctx: &mut Context, x: f32, y: f32, dx: f32, dy: f32) {
+        if self.panning {
+            self.pan.0 += dx * dpi_scale(ctx);
+            self.pan.1 += dy * dpi_scale(ctx);
+            self.clamp_pan(ctx);
+            self.changed();
+            return;
+        }
+        let (x, y) = (x * dpi_scale(ctx), y * dpi_scale(ctx));
+        if let Some(index) = self.menu_entry_at(ctx, x, y) {
+            if let Some(menu) = &mut self.menu {
+                if menu.selected != index {
+                    menu.selected = index;
+                    self.dirty = true;
                 }
             }
-        } else {
-            if self.grid[col][row].is_some() {
-                return;
+        }
+    }
+
+    fn mouse_button_up_event(&mut self, ctx: &mut Context, btn: MouseButton, x: f32, y: f32) {
+        if btn == MouseButton::Middle {
+            self.panning = false;
+            return;
+        }
+        // Mouse positions arrive in logical pixels, whereas the board is laid
+        // out in physical pixels.
+        let (x, y) = (x * dpi_scale(ctx), y * dpi_scale(ctx));
+        if self.menu.is_some() {
+            if let Some(index) = self.menu_entry_at(ctx, x, y) {
+                if let Some(menu) = &mut self.menu {
+                    menu.selected = index;
+                }
+                if let Some(action) = self.menu_action() {
+                    self.activate(ctx, action);
+                }
             }
-            self.grid[col][row] = Some(self.turn);
-        }
-        for (forward, backward) in &[
-            ((1, 0), (-1, 0)),
-            ((0, 1), (0, -1)),
-            ((1, 1), (-1, -1)),
-            ((-1, 1), (1, -1)),
-        ] {
-            let forward_count =
-                self.check_direction(col as i32, row as i32, forward.0, forward.1, self.turn);
-            let backward_count =
-                self.check_direction(col as i32, row as i32, backward.0, backward.1, self.turn);
-            let count = forward_count + backward_count + 1;
-            if count >= self.win {
-                self.winner = Some((
-                    Player::Crosses,
-                    // Calculate the coordinates of the start cell and the end cell.
-                    Axis(
-                        (
-                            (col as i32 + forward.0 * forward_count as i32).max(0) as usize,
-                            (row as i32 + forward.1 * forward_count as i32).max(0) as usize,
-                        ),
-                        (
-                            (col as i32 + backward.0 * backward_count as i32).max(0) as usize,
-                            (row as i32 + backward.1 * backward_count as i32).max(0) as usize,
-                        ),
-                    ),
-                ));
-                break;
-            }
-        }
-        self.turn = match self.turn {
-            Player::Naughts => Player::Crosses,
-            Player::Crosses => Player::Naughts,
-        };
+            return;
+        }
+        if self.viewer.is_some() {
+            return;
+        }
+        let board = self.board_rect(ctx);
+        if !board.contains([x, y]) {
+            return;
+        }
+        // Clamp so that a click on the far edge stays within the grid.
+        let col =
+            (((x - board.x) / board.w * self.game.size as f32) as usize).min(self.game.size - 1);
+        let row =
+            (((y - board.y) / board.h * self.game.size as f32) as usize).min(self.game.size - 1);
+        if self.cursor.take().is_some() {
+            self.dirty = true;
+        }
+        self.choose(col, row);
     }
 
     fn draw(&mut self, ctx: &mut ggez::Context) -> ggez::GameResult {
-        graphics::clear(ctx, [0.0, 0.0, 0.0, 0.0].into());
-        let mut mb = MeshBuilder::new();
-        self.build_grid(ctx, &mut mb)?;
-        self.build_players(ctx, &mut mb)?;
-        if self.winner.is_some() {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.dirty = false;
+        let title = self.title();
+        if title != self.title {
+            graphics::set_window_title(ctx, &title);
+            self.title = title;
+        }
+        graphics::clear(ctx, self.theme.background.into());
+        if self.menu.is_some() {
+            self.draw_menu(ctx)?;
+            self.draw_notice(ctx)?;
+            graphics::present(ctx)?;
+            self.last_frame = Instant::now();
+            return Ok(());
+        }
+        // Meshes cannot be built without any geometry, so each layer is only
+        // built when it has something to draw.
+        let key = (
+            self.board_rect(ctx),
+            self.theme.grid_color(self.shown().turn),
+        );
+        if self
+            .grid_mesh
+            .as_ref()
+            .map_or(true, |(built, _)| *built != key)
+        {
+            self.grid_mesh = if self.shown().size > 1 {
+                self.rebuilds += 1;
+                let mut mb = MeshBuilder::new();
+                self.build_grid(ctx, &mut mb)?;
+                Some((key, mb.build(ctx)?))
+            } else {
+                None
+            };
+        }
+        if self.pieces_stale {
+            self.pieces_stale = false;
+            self.pieces_mesh = if !self.shown().grid.is_empty() {
+                self.rebuilds += 1;
+                let mut mb = MeshBuilder::new();
+                self.build_players(ctx, &mut mb)?;
+                Some(mb.build(ctx)?)
+            } else {
+                None
+            };
+            self.batches = if self.batched() && !self.shown().grid.is_empty() {
+                Some(self.build_batches(ctx)?)
+            } else {
+                None
+            };
+        }
+        let cursor = self.viewer.is_none() && self.game.in_progress() && self.cursor.is_some();
+        let clock = self.viewer.is_none() && self.clock.as_ref().and_then(Clock::running).is_some();
+        // Skinned hints and pending moves are drawn with the pieces.
+        let hint = self.viewer.is_none() && self.hint.is_some() && self.sprites.is_none();
+        let pending = self.viewer.is_none() && self.pending.is_some() && self.sprites.is_none();
+        let overlay = if cursor
+            || clock
+            || hint
+            || pending
+            || self.viewer.is_some()
+            || self.shown().winner.is_some()
+        {
+            let mut mb = MeshBuilder::new();
+            if hint {
+                self.build_hint(ctx, &mut mb)?;
+            }
+            if pending {
+                self.build_pending(ctx, &mut mb)?;
+            }
+            if clock {
+                self.build_clock(ctx, &mut mb)?;
+            }
+            if cursor {
+                self.build_cursor(ctx, &mut mb)?;
+            }
+            self.build_scrubber(ctx, &mut mb)?;
             self.build_throughline(ctx, &mut mb)?;
+            Some(mb.build(ctx)?)
+        } else {
+            None
+        };
+        if let Some((_, mesh)) = &self.grid_mesh {
+            graphics::draw(ctx, mesh, graphics::DrawParam::default())?;
         }
-        let mesh = mb.build(ctx)?;
-        graphics::draw(ctx, &mesh, graphics::DrawParam::default())?;
+        if let Some(mesh) = &self.pieces_mesh {
+            graphics::draw(ctx, mesh, graphics::DrawParam::default())?;
+        }
+        if let Some((naughts, crosses)) = &self.batches {
+            graphics::draw(ctx, naughts, graphics::DrawParam::default())?;
+            graphics::draw(ctx, crosses, graphics::DrawParam::default())?;
+        }
+        self.draw_sprites(ctx)?;
+        if let Some(mesh) = &overlay {
+            graphics::draw(ctx, mesh, graphics::DrawParam::default())?;
+        }
+        if self.settings.coordinates {
+            self.draw_coordinates(ctx)?;
+        }
+        self.draw_score(ctx)?;
+        if self.analysing {
+            self.draw_analysis(ctx)?;
+        }
+        if self.debug {
+            self.draw_debug(ctx)?;
+        }
+        self.draw_names(ctx)?;
+        self.draw_notice(ctx)?;
         graphics::present(ctx)?;
+        self.last_frame = Instant::now();
         Ok(())
     }
 }
 
-use clap::{App, Arg};
+// Ratio of physical to logical pixels. Layout is done in physical pixels, so
+// sizes given in logical pixels are multiplied by this to look the same on
+// every display.
+fn dpi_scale(ctx: &ggez::Context) -> f32 {
+    graphics::os_hidpi_factor(ctx)
+}
+
+// Today's puzzle, from the built-in puzzles and any in the puzzle directory.
+// Puzzles that cannot be read are left out of the pool.
+fn daily_puzzle(ctx: &mut Context) -> Puzzle {
+    let mut pool = Puzzle::pack();
+    if let Ok(paths) = filesystem::read_dir(ctx, PUZZLE_DIR) {
+        let mut paths: Vec<PathBuf> = paths.collect();
+        paths.sort();
+        for path in paths {
+            let mut text = String::new();
+            if let Ok(mut file) = filesystem::open(ctx, &path) {
+                if file.read_to_string(&mut text).is_ok() {
+                    pool.extend(Puzzle::parse(&text).ok());
+                }
+            }
+        }
+    }
+    Puzzle::daily(&pool, timestamp() / 86_400)
+        .cloned()
+        .expect("the built-in puzzles are never empty")
+}
+
+// Seconds since the Unix epoch, used to give saved files unique names.
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+// Maps screen coordinates one to one onto physical pixels, which is what
+// `drawable_size` reports and the layout is computed in.
+fn fit_screen_coordinates(ctx: &mut ggez::Context) -> ggez::GameResult {
+    let (w, h) = graphics::drawable_size(ctx);
+    graphics::set_screen_coordinates(ctx, graphics::Rect::new(0.0, 0.0, w, h))
+}
+
+// Text for the HUD, scaled by `scale` from the base text size.
+fn overlay_text(contents: String, scale: f32) -> graphics::Text {
+    let mut text = graphics::Text::new(contents);
+    text.set_font(
+        graphics::Font::default(),
+        graphics::Scale::uniform(TEXT_SIZE * scale),
+    );
+    text
+}
+
+// The theme for the given mode. Streamer overlays are drawn on a chroma key
+// background whatever the mode, so they can be keyed out when captured.
+fn mode_theme(base: &Theme, mode: Mode, overlay: bool) -> Theme {
+    let mut theme = base.for_mode(mode);
+    if overlay {
+        theme.background = CHROMA_KEY;
+    }
+    theme
+}
+
+use clap::{App, Arg, SubCommand};
 
 pub fn main() -> ggez::GameResult {
     let matches = App::new("Tick Tack Toe")
@@ -233,14 +2469,14 @@ pub fn main() -> ggez::GameResult {
                 .takes_value(true)
                 .long("size")
                 .short("s")
-                .help("Size of grid."),
+                .help("Size of grid. Defaults to the size in settings.toml, or 3."),
         )
         .arg(
             Arg::with_name("win")
                 .takes_value(true)
                 .long("win")
                 .short("w")
-                .help("Number of aligned pieces required to win the game."),
+                .help("Number of aligned pieces required to win the game. Defaults to the win in settings.toml, or 3."),
         )
         .arg(
             Arg::with_name("gravity")
@@ -249,21 +2485,459 @@ pub fn main() -> ggez::GameResult {
                 .short("g")
                 .help("Simulate gravity when placing a piece."),
         )
+        .arg(
+            Arg::with_name("no-gravity")
+                .takes_value(false)
+                .long("no-gravity")
+                .conflicts_with("gravity")
+                .help("Place pieces where they are played, even if the settings file turns gravity on."),
+        )
+        .arg(
+            Arg::with_name("theme")
+                .takes_value(true)
+                .long("theme")
+                .short("t")
+                .help("Path to a theme file. Defaults to theme.toml in the config directory."),
+        )
+        .arg(
+            Arg::with_name("palette")
+                .takes_value(true)
+                .long("palette")
+                .possible_values(Palette::NAMES)
+                .help("Color blind friendly piece colors, overriding the theme."),
+        )
+        .arg(
+            Arg::with_name("shapes")
+                .takes_value(false)
+                .long("shapes")
+                .help("Draw pieces as triangles and squares rather than naughts and crosses."),
+        )
+        .arg(
+            Arg::with_name("resources")
+                .takes_value(true)
+                .long("resources")
+                .short("r")
+                .help("Additional directory to load resources such as skin images from."),
+        )
+        .arg(
+            Arg::with_name("naughts")
+                .takes_value(true)
+                .long("naughts")
+                .help("Name of the player using naughts."),
+        )
+        .arg(
+            Arg::with_name("crosses")
+                .takes_value(true)
+                .long("crosses")
+                .help("Name of the player using crosses."),
+        )
+        .arg(
+            Arg::with_name("replay-delay")
+                .takes_value(true)
+                .long("replay-delay")
+                .help("Milliseconds between moves when autoplaying a replay."),
+        )
+        .arg(
+            Arg::with_name("clock")
+                .takes_value(true)
+                .long("clock")
+                .short("c")
+                .help("Seconds each player has for a round, optionally followed by + and seconds added after each move, such as 60+2. A player who runs out of time loses."),
+        )
+        .arg(
+            Arg::with_name("clock-delay")
+                .takes_value(true)
+                .long("clock-delay")
+                .help("Seconds each move may take before the player's clock starts running down."),
+        )
+        .arg(
+            Arg::with_name("no-vsync")
+                .takes_value(false)
+                .long("no-vsync")
+                .help("Present frames without waiting for the display's refresh."),
+        )
+        .arg(
+            Arg::with_name("max-fps")
+                .takes_value(true)
+                .long("max-fps")
+                .help("Draw at most this many frames per second."),
+        )
+        .arg(
+            Arg::with_name("next-round")
+                .takes_value(true)
+                .long("next-round")
+                .help("Start the next round this many seconds after each round ends."),
+        )
+        .arg(
+            Arg::with_name("narrate")
+                .takes_value(true)
+                .min_values(0)
+                .long("narrate")
+                .help("Announce moves and results with the board as text, to stdout or to the given file."),
+        )
+        .arg(
+            Arg::with_name("puzzle")
+                .takes_value(true)
+                .min_values(0)
+                .long("puzzle")
+                .help("Solve the puzzle in the given file, or today's built-in puzzle."),
+        )
+        .arg(
+            Arg::with_name("overlay")
+                .takes_value(false)
+                .long("overlay")
+                .help("Draw for capture as a stream overlay: a chroma key background and a larger HUD."),
+        )
+        .arg(
+            Arg::with_name("replay")
+                .takes_value(true)
+                .long("replay")
+                .help("Path to a saved game to open in the replay viewer."),
+        )
+        .arg(
+            Arg::with_name("record")
+                .takes_value(true)
+                .long("record")
+                .conflicts_with("replay")
+                .help("Path to a game record, as exported with R, to open in the replay viewer."),
+        )
+        .arg(
+            Arg::with_name("headless")
+                .takes_value(false)
+                .long("headless")
+                .help("Play without a window, reading moves such as b3 from stdin and printing the board to stdout."),
+        )
+        .arg(
+            Arg::with_name("best-of")
+                .takes_value(true)
+                .long("best-of")
+                .short("b")
+                .help("Play a match of this many rounds, resetting the score once it is decided."),
+        )
+        .subcommand(
+            SubCommand::with_name("bench")
+                .about("Times the game engine on boards of several sizes and prints a table."),
+        )
+        .subcommand(
+            SubCommand::with_name("review")
+                .about("Has the computer review a game record and prints the annotated moves.")
+                .arg(
+                    Arg::with_name("record")
+                        .required(true)
+                        .help("Path to a game record, as exported with R."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("puzzles")
+                .about("Finds puzzles on the board given by the rules options by having the AI play itself.")
+                .arg(
+                    Arg::with_name("moves")
+                        .takes_value(true)
+                        .long("moves")
+                        .help("Number of moves the puzzles are won in. Defaults to 2."),
+                )
+                .arg(
+                    Arg::with_name("count")
+                        .takes_value(true)
+                        .long("count")
+                        .help("Number of puzzles to find. Defaults to 1."),
+                )
+                .arg(
+                    Arg::with_name("seed")
+                        .takes_value(true)
+                        .long("seed")
+                        .help("Seed for the games played, to find the same puzzles again."),
+                )
+                .arg(
+                    Arg::with_name("out")
+                        .takes_value(true)
+                        .long("out")
+                        .help("Directory to write each puzzle to as a file, rather than printing them."),
+                ),
+        )
         .get_matches();
+    if matches.subcommand_matches("bench").is_some() {
+        bench::run(std::io::stdout())?;
+        return Ok(());
+    }
+    if let Some(sub) = matches.subcommand_matches("review") {
+        let path = sub.value_of("record").expect("record is required");
+        let record = Record::parse(&std::fs::read_to_string(path)?)
+            .map_err(|err| ggez::GameError::ConfigError(format!("parsing game record: {}", err)))?;
+        print!("{}", Review::new(&record.replay));
+        return Ok(());
+    }
+    // The rules fall back on the settings file, then on a classic 3x3 board.
     let size = matches
         .value_of("size")
-        .unwrap_or("3")
-        .parse::<usize>()
-        .expect("parsing size value");
+        .map(|value| value.parse::<usize>().expect("parsing size value"));
     let win = matches
         .value_of("win")
-        .unwrap_or("3")
-        .parse::<usize>()
-        .expect("parsing win value");
-    let gravity = matches.is_present("gravity");
-    let cb = ggez::ContextBuilder::new("Tick Tack Toe", "Jack Mordaunt")
-        .window_setup(ggez::conf::WindowSetup::default().vsync(true));
+        .map(|value| value.parse::<usize>().expect("parsing win value"));
+    let gravity = if matches.is_present("gravity") {
+        Some(true)
+    } else if matches.is_present("no-gravity") {
+        Some(false)
+    } else {
+        None
+    };
+    if let Some(sub) = matches.subcommand_matches("puzzles") {
+        let moves = sub
+            .value_of("moves")
+            .unwrap_or("2")
+            .parse::<usize>()
+            .expect("parsing moves value");
+        let count = sub
+            .value_of("count")
+            .unwrap_or("1")
+            .parse::<usize>()
+            .expect("parsing count value");
+        let seed = sub
+            .value_of("seed")
+            .map(|value| value.parse::<u64>().expect("parsing seed value"))
+            .unwrap_or_else(timestamp);
+        let generator = Generator::new(
+            size.unwrap_or(3),
+            win.unwrap_or(3),
+            gravity.unwrap_or(false),
+            moves,
+        )
+        .seed(seed);
+        let mut found = 0;
+        for puzzle in generator.take(count) {
+            found += 1;
+            match sub.value_of("out") {
+                Some(dir) => {
+                    let name = format!("puzzle-{}-{}.txt", seed, found);
+                    std::fs::write(Path::new(dir).join(name), puzzle.to_string())?;
+                }
+                None => println!("{}", puzzle),
+            }
+        }
+        if found < count {
+            eprintln!("Found {} of {} puzzles before giving up.", found, count);
+        }
+        return Ok(());
+    }
+    let best_of = matches
+        .value_of("best-of")
+        .map(|value| value.parse::<usize>().expect("parsing best-of value"));
+    let mut names = (
+        matches.value_of("naughts").unwrap_or("Naughts").to_owned(),
+        matches.value_of("crosses").unwrap_or("Crosses").to_owned(),
+    );
+    if matches.is_present("headless") {
+        // Any message on stdout would be taken for a move, so a bad settings
+        // file is reported on stderr.
+        let settings = Settings::load_headless().unwrap_or_else(|err| {
+            eprintln!("Using the default settings: {}", err);
+            Settings::default()
+        });
+        let stdin = std::io::stdin();
+        headless::run(
+            stdin.lock(),
+            std::io::stdout(),
+            Game::new(
+                size.or(settings.size).unwrap_or(3),
+                win.or(settings.win).unwrap_or(3),
+                gravity.or(settings.gravity).unwrap_or(false),
+            ),
+            &names,
+        )?;
+        return Ok(());
+    }
+    let mut cb = ggez::ContextBuilder::new(settings::GAME_ID, settings::AUTHOR)
+        .window_setup(ggez::conf::WindowSetup::default().vsync(!matches.is_present("no-vsync")))
+        .window_mode(ggez::conf::WindowMode::default().resizable(true));
+    if let Some(path) = matches.value_of("resources") {
+        cb = cb.add_resource_path(path);
+    }
     let (ctx, event_loop) = &mut cb.build()?;
-    let state = &mut MainState::new(size, win, gravity)?;
-    event::run(ctx, event_loop, state)
+    let (mut settings, settings_error) = match Settings::load(ctx) {
+        Ok(settings) => (settings, None),
+        Err(err) => (Settings::default(), Some(err)),
+    };
+    settings.restore_window(ctx)?;
+    fit_screen_coordinates(ctx)?;
+    let size = size.or(settings.size).unwrap_or(3);
+    let win = win.or(settings.win).unwrap_or(3);
+    let gravity = gravity.or(settings.gravity).unwrap_or(false);
+    // The theme is remembered by its full path, so it is still found when the
+    // game is started from another directory.
+    if let Some(path) = matches.value_of("theme") {
+        settings.theme = Some(std::fs::canonicalize(path)?);
+    }
+    // Only a theme asked for on the command line is worth failing over.
+    let (mut theme, theme_error) = match Theme::load(ctx, settings.theme.as_deref()) {
+        Ok(theme) => (theme, None),
+        Err(err) if !matches.is_present("theme") => (Theme::default(), Some(err.to_string())),
+        Err(err) => return Err(err),
+    };
+    if let Some(palette) = matches.value_of("palette").and_then(Palette::from_name) {
+        theme.set_palette(palette);
+    }
+    if matches.is_present("shapes") {
+        theme.marks = Marks::Shapes;
+    }
+    let replay_delay = matches
+        .value_of("replay-delay")
+        .unwrap_or("1000")
+        .parse::<u64>()
+        .map(Duration::from_millis)
+        .expect("parsing replay-delay value");
+    let clock = matches.value_of("clock").map(|value| TimeControl {
+        delay: matches
+            .value_of("clock-delay")
+            .map(|value| value.parse::<u64>().expect("parsing clock-delay value"))
+            .map_or(Duration::from_secs(0), Duration::from_secs),
+        ..TimeControl::parse(value).expect("parsing clock value")
+    });
+    let next_round = matches
+        .value_of("next-round")
+        .map(|value| value.parse::<u64>().expect("parsing next-round value"))
+        .or(settings.next_round)
+        .map(Duration::from_secs);
+    let max_fps = matches
+        .value_of("max-fps")
+        .map(|value| value.parse::<u32>().expect("parsing max-fps value"));
+    let mut replay = match matches.value_of("replay") {
+        Some(path) => Some(Replay::read(Path::new(path))?),
+        None => None,
+    };
+    if let Some(path) = matches.value_of("record") {
+        let record = Record::parse(&std::fs::read_to_string(path)?)
+            .map_err(|err| ggez::GameError::ConfigError(format!("parsing game record: {}", err)))?;
+        names = record.names;
+        replay = Some(record.replay);
+    }
+    let puzzle = match matches.value_of("puzzle") {
+        Some(path) => Some(
+            Puzzle::parse(&std::fs::read_to_string(path)?)
+                .map_err(|err| ggez::GameError::ConfigError(format!("parsing puzzle: {}", err)))?,
+        ),
+        None if matches.is_present("puzzle") => Some(daily_puzzle(ctx)),
+        None => None,
+    };
+    let narrator: Option<Box<dyn Write>> = match matches.value_of("narrate") {
+        Some(path) => Some(Box::new(
+            OpenOptions::new().create(true).append(true).open(path)?,
+        )),
+        None if matches.is_present("narrate") => Some(Box::new(std::io::stdout())),
+        None => None,
+    };
+    let options = Options {
+        size: size,
+        win: win,
+        gravity: gravity,
+        best_of: best_of,
+        names: names,
+        replay_delay: replay_delay,
+        clock: clock,
+        next_round: next_round,
+        max_fps: max_fps,
+        narrator: narrator,
+        overlay: matches.is_present("overlay"),
+        replay: replay,
+        puzzle: puzzle,
+        settings_error: settings_error,
+        theme_error: theme_error,
+    };
+    let state = &mut MainState::new(ctx, options, theme, settings)?;
+    run(ctx, event_loop, state)
+}
+
+// Runs the game the way `event::run` does, but also opens replays dropped onto
+// the window, which ggez does not pass on to the event handler. Gamepad events
+// go straight to the state, as ggez gives no way to name a `GamepadId`.
+fn run(
+    ctx: &mut Context,
+    events: &mut event::EventsLoop,
+    state: &mut MainState,
+) -> ggez::GameResult {
+    use ggez::event::winit_event::{
+        ElementState, Event, KeyboardInput, MouseScrollDelta, WindowEvent,
+    };
+    use ggez::event::EventHandler;
+    use ggez::input::gamepad::gilrs;
+    use ggez::input::{keyboard, mouse};
+
+    while ctx.continuing {
+        ctx.timer_context.tick();
+        events.poll_events(|event| {
+            ctx.process_event(&event);
+            let event = match event {
+                Event::WindowEvent { event, .. } => event,
+                _ => return,
+            };
+            match event {
+                WindowEvent::Resized(size) => {
+                    state.resize_event(ctx, size.width as f32, size.height as f32)
+                }
+                WindowEvent::CloseRequested if !state.quit_event(ctx) => event::quit(ctx),
+                WindowEvent::DroppedFile(path) => state.open_dropped(&path),
+                WindowEvent::Focused(gained) => state.focus_event(ctx, gained),
+                WindowEvent::ReceivedCharacter(ch) => state.text_input_event(ctx, ch),
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state: ElementState::Pressed,
+                            virtual_keycode: Some(code),
+                            modifiers,
+                            ..
+                        },
+                    ..
+                } => {
+                    let repeat = keyboard::is_key_repeated(ctx);
+                    state.key_down_event(ctx, code, modifiers.into(), repeat);
+                }
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state: ElementState::Released,
+                            virtual_keycode: Some(code),
+                            modifiers,
+                            ..
+                        },
+                    ..
+                } => state.key_up_event(ctx, code, modifiers.into()),
+                WindowEvent::MouseWheel { delta, .. } => match delta {
+                    MouseScrollDelta::LineDelta(x, y) => state.mouse_wheel_event(ctx, x, y),
+                    MouseScrollDelta::PixelDelta(position) => {
+                        state.mouse_wheel_event(ctx, position.x as f32, position.y as f32)
+                    }
+                },
+                WindowEvent::MouseInput {
+                    state: pressed,
+                    button,
+                    ..
+                } => {
+                    let position = mouse::position(ctx);
+                    match pressed {
+                        ElementState::Pressed => {
+                            state.mouse_button_down_event(ctx, button, position.x, position.y)
+                        }
+                        ElementState::Released => {
+                            state.mouse_button_up_event(ctx, button, position.x, position.y)
+                        }
+                    }
+                }
+                WindowEvent::CursorMoved { .. } => {
+                    let position = mouse::position(ctx);
+                    let delta = mouse::delta(ctx);
+                    state.mouse_motion_event(ctx, position.x, position.y, delta.x, delta.y);
+                }
+                _ => {}
+            }
+        });
+        while let Some(gilrs::Event { event, .. }) = ctx.gamepad_context.next_event() {
+            match event {
+                gilrs::EventType::ButtonPressed(btn, _) => state.gamepad_button(ctx, btn),
+                gilrs::EventType::AxisChanged(axis, value, _) => state.gamepad_axis(axis, value),
+                _ => {}
+            }
+        }
+        state.update(ctx)?;
+        state.draw(ctx)?;
+    }
+    Ok(())
 }
@@ -0,0 +1,123 @@
+use crate::settings::{FirstMove, Settings};
+use crate::theme::Mode;
+use crate::trainer::Opening;
+
+/// What choosing a menu entry does.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Action {
+    /// Close the menu and play on the local board.
+    Play,
+    /// Resume the game that was interrupted when the last session ended.
+    Recover,
+    /// Set today's built-in puzzle on the board.
+    Puzzle,
+    /// Quiz the player on the best responses to openings.
+    Train(Opening),
+    /// Open the settings page.
+    Settings,
+    Quit,
+    ToggleMode,
+    ToggleConfirm,
+    ToggleFirstMove,
+    /// Return from the settings page to the main page.
+    Back,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Page {
+    Main,
+    Settings,
+}
+
+/// The start screen, navigated with the keyboard, mouse or gamepad.
+#[derive(Clone, Debug)]
+pub struct Menu {
+    pub page: Page,
+    /// Index of the highlighted entry on the current page.
+    pub selected: usize,
+}
+
+impl Default for Menu {
+    fn default() -> Self {
+        Menu::new()
+    }
+}
+
+impl Menu {
+    pub fn new() -> Menu {
+        Menu {
+            page: Page::Main,
+            selected: 0,
+        }
+    }
+
+    /// The labels and actions of the entries on the current page. Settings
+    /// entries show the current value of the setting, and the main page offers
+    /// to restore an interrupted game when there is one.
+    pub fn entries(&self, settings: &Settings, recoverable: bool) -> Vec<(String, Action)> {
+        match self.page {
+            Page::Main => {
+                let mut entries = vec![];
+                if recoverable {
+                    entries.push(("Restore interrupted game".to_owned(), Action::Recover));
+                }
+                entries.push(("Local game".to_owned(), Action::Play));
+                entries.push(("Daily puzzle".to_owned(), Action::Puzzle));
+                entries.push((
+                    "Opening trainer".to_owned(),
+                    Action::Train(Opening::Classic),
+                ));
+                entries.push((
+                    "Gravity opening trainer".to_owned(),
+                    Action::Train(Opening::Gravity),
+                ));
+                entries.push(("Settings".to_owned(), Action::Settings));
+                entries.push(("Quit".to_owned(), Action::Quit));
+                entries
+            }
+            Page::Settings => vec![
+                (
+                    format!(
+                        "Mode: {}",
+                        match settings.mode {
+                            Mode::Dark => "Dark",
+                            Mode::Light => "Light",
+                        }
+                    ),
+                    Action::ToggleMode,
+                ),
+                (
+                    format!(
+                        "Confirm moves: {}",
+                        if settings.confirm_moves { "On" } else { "Off" }
+                    ),
+                    Action::ToggleConfirm,
+                ),
+                (
+                    format!(
+                        "First move: {}",
+                        match settings.first_move {
+                            FirstMove::Alternate => "Alternate",
+                            FirstMove::Naughts => "Naughts",
+                        }
+                    ),
+                    Action::ToggleFirstMove,
+                ),
+                ("Back".to_owned(), Action::Back),
+            ],
+        }
+    }
+
+    /// Move the highlight by `delta` entries, wrapping around the page.
+    pub fn step(&mut self, delta: i32, count: usize) {
+        if count > 0 {
+            self.selected = (self.selected as i32 + delta).rem_euclid(count as i32) as usize;
+        }
+    }
+
+    /// Switch to the given page, highlighting its first entry.
+    pub fn open(&mut self, page: Page) {
+        self.page = page;
+        self.selected = 0;
+    }
+}
@@ -0,0 +1,44 @@
+use ggez::filesystem;
+use ggez::Context;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use crate::replay::Replay;
+
+/// Saving and loading replays as TOML, in the user data directory or
+/// anywhere on disk.
+pub trait ReplayFile: Sized {
+    /// Read a replay saved with `save` from the given path in the user data
+    /// directory.
+    fn load(ctx: &mut Context, path: &str) -> ggez::GameResult<Self>;
+
+    /// Read a replay saved with `save` from anywhere on disk, such as a path
+    /// given on the command line.
+    fn read(path: &Path) -> ggez::GameResult<Self>;
+
+    fn save(&self, ctx: &mut Context, path: &str) -> ggez::GameResult;
+}
+
+impl ReplayFile for Replay {
+    fn load(ctx: &mut Context, path: &str) -> ggez::GameResult<Replay> {
+        let mut contents = String::new();
+        filesystem::open(ctx, path)?.read_to_string(&mut contents)?;
+        parse(&contents)
+    }
+
+    fn read(path: &Path) -> ggez::GameResult<Replay> {
+        parse(&std::fs::read_to_string(path)?)
+    }
+
+    fn save(&self, ctx: &mut Context, path: &str) -> ggez::GameResult {
+        let contents = toml::to_string(self)
+            .map_err(|err| ggez::GameError::ConfigError(format!("encoding replay: {}", err)))?;
+        filesystem::create(ctx, path)?.write_all(contents.as_bytes())?;
+        Ok(())
+    }
+}
+
+fn parse(contents: &str) -> ggez::GameResult<Replay> {
+    toml::from_str(contents)
+        .map_err(|err| ggez::GameError::ConfigError(format!("parsing replay: {}", err)))
+}
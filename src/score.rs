@@ -0,0 +1,80 @@
+use crate::game::Player;
+
+/// Running tally of results across the rounds of a session.
+///
+/// When `best_of` is set the rounds form a match, which is decided once either
+/// player has won more than half of the rounds.
+#[derive(Clone, Debug, Default)]
+pub struct Score {
+    pub naughts: usize,
+    pub crosses: usize,
+    pub draws: usize,
+    pub best_of: Option<usize>,
+}
+
+impl Score {
+    pub fn new(best_of: Option<usize>) -> Score {
+        Score {
+            best_of: best_of,
+            ..Score::default()
+        }
+    }
+
+    /// Record the result of a round, `None` being a draw.
+    pub fn record(&mut self, winner: Option<Player>) {
+        match winner {
+            Some(Player::Naughts) => self.naughts += 1,
+            Some(Player::Crosses) => self.crosses += 1,
+            None => self.draws += 1,
+        }
+    }
+
+    /// Remove a result previously recorded, such as when the move that ended
+    /// the round is taken back.
+    pub fn unrecord(&mut self, winner: Option<Player>) {
+        let count = match winner {
+            Some(Player::Naughts) => &mut self.naughts,
+            Some(Player::Crosses) => &mut self.crosses,
+            None => &mut self.draws,
+        };
+        *count = count.saturating_sub(1);
+    }
+
+    pub fn wins(&self, player: Player) -> usize {
+        match player {
+            Player::Naughts => self.naughts,
+            Player::Crosses => self.crosses,
+        }
+    }
+
+    /// The player who has clinched the match, if any.
+    pub fn match_winner(&self) -> Option<Player> {
+        let best_of = self.best_of?;
+        [Player::Naughts, Player::Crosses]
+            .iter()
+            .cloned()
+            .find(|player| self.wins(*player) > best_of / 2)
+    }
+
+    /// Clear the tally for a new match.
+    pub fn reset(&mut self) {
+        *self = Score::new(self.best_of);
+    }
+
+    /// One line summary suitable for display.
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "O {}  X {}  Draws {}",
+            self.naughts, self.crosses, self.draws
+        );
+        if let Some(best_of) = self.best_of {
+            summary.push_str(&format!("  (best of {})", best_of));
+        }
+        match self.match_winner() {
+            Some(Player::Naughts) => summary.push_str("  O wins the match"),
+            Some(Player::Crosses) => summary.push_str("  X wins the match"),
+            None => {}
+        }
+        summary
+    }
+}
@@ -0,0 +1,138 @@
+use ggez::filesystem;
+use ggez::graphics;
+use ggez::Context;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+use crate::keymap::KeyBindings;
+use crate::theme::Mode;
+use crate::trainer::Progress;
+
+/// Name of the settings file in the user config directory.
+const SETTINGS_FILE: &str = "/settings.toml";
+
+/// Game id and author the user directories are named after.
+pub const GAME_ID: &str = "Tick Tack Toe";
+pub const AUTHOR: &str = "Jack Mordaunt";
+
+/// Who moves first in each new round.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub enum FirstMove {
+    /// The players take turns to move first, so neither has the advantage
+    /// over a session.
+    #[default]
+    Alternate,
+    /// Naughts always moves first.
+    Naughts,
+}
+
+impl FirstMove {
+    pub fn toggle(self) -> FirstMove {
+        match self {
+            FirstMove::Alternate => FirstMove::Naughts,
+            FirstMove::Naughts => FirstMove::Alternate,
+        }
+    }
+}
+
+/// User preferences that persist between sessions.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Settings {
+    pub mode: Mode,
+    /// Whether a piece is only placed once its cell is chosen a second time,
+    /// guarding against misclicks on dense boards.
+    pub confirm_moves: bool,
+    /// Whether column letters and row numbers are shown along the board edges.
+    pub coordinates: bool,
+    pub first_move: FirstMove,
+    /// Size of the grid when none is given on the command line.
+    pub size: Option<usize>,
+    /// Number of aligned pieces needed to win when none is given on the
+    /// command line.
+    pub win: Option<usize>,
+    /// Whether pieces fall when neither `--gravity` nor `--no-gravity` is
+    /// given.
+    pub gravity: Option<bool>,
+    /// Seconds after a round ends before the next one starts on its own, when
+    /// `--next-round` is not given. Rounds only restart on request if unset.
+    pub next_round: Option<u64>,
+    /// Theme file last given on the command line, used when none is given.
+    pub theme: Option<PathBuf>,
+    /// Logical size of the window when the last session ended.
+    pub window_size: Option<(f32, f32)>,
+    /// Logical position of the window when the last session ended.
+    pub window_position: Option<(f64, f64)>,
+    // Tables come last, as TOML has no way to write a plain value after one.
+    pub keys: KeyBindings,
+    /// How often the opening trainer's questions have been answered
+    /// correctly.
+    pub trainer: Progress,
+}
+
+impl Settings {
+    /// Load the settings saved by a previous session, or the defaults if
+    /// there are none. Unreadable settings fail with a description of the
+    /// problem, so the caller can fall back to the defaults and say why.
+    pub fn load(ctx: &mut Context) -> Result<Settings, String> {
+        if !filesystem::exists(ctx, SETTINGS_FILE) {
+            return Ok(Settings::default());
+        }
+        read(filesystem::open(ctx, SETTINGS_FILE))
+    }
+
+    /// Load the settings without a window, for modes such as `--headless`
+    /// that never build a context.
+    pub fn load_headless() -> Result<Settings, String> {
+        let mut fs = filesystem::Filesystem::new(GAME_ID, AUTHOR)
+            .map_err(|err| format!("reading settings: {}", err))?;
+        if !fs.exists(SETTINGS_FILE) {
+            return Ok(Settings::default());
+        }
+        read(fs.open(SETTINGS_FILE))
+    }
+
+    /// Read settings as written by `to_toml`. Settings left out take their
+    /// defaults.
+    pub fn parse(text: &str) -> Result<Settings, String> {
+        toml::from_str(text).map_err(|err| format!("parsing settings: {}", err))
+    }
+
+    pub fn to_toml(&self) -> Result<String, String> {
+        toml::to_string(self).map_err(|err| format!("encoding settings: {}", err))
+    }
+
+    /// Move and resize the window to where it was when the last session ended.
+    pub fn restore_window(&self, ctx: &mut Context) -> ggez::GameResult {
+        if let Some((width, height)) = self.window_size {
+            graphics::set_drawable_size(ctx, width, height)?;
+        }
+        if let Some(position) = self.window_position {
+            graphics::window(ctx).set_position(position.into());
+        }
+        Ok(())
+    }
+
+    /// Record the current window position so it can be restored next session.
+    /// The size is recorded as the window is resized.
+    pub fn remember_window(&mut self, ctx: &Context) {
+        if let Some(position) = graphics::window(ctx).get_position() {
+            self.window_position = Some((position.x, position.y));
+        }
+    }
+
+    pub fn save(&self, ctx: &mut Context) -> ggez::GameResult {
+        let contents = self.to_toml().map_err(ggez::GameError::ConfigError)?;
+        filesystem::create(ctx, SETTINGS_FILE)?.write_all(contents.as_bytes())?;
+        Ok(())
+    }
+}
+
+// Reads and parses an opened settings file.
+fn read(file: ggez::GameResult<filesystem::File>) -> Result<Settings, String> {
+    let mut contents = String::new();
+    file.and_then(|mut file| Ok(file.read_to_string(&mut contents)?))
+        .map_err(|err| format!("reading settings: {}", err))?;
+    Settings::parse(&contents)
+}
@@ -0,0 +1,101 @@
+use std::time::{Duration, Instant};
+
+use crate::clock;
+use crate::game::Player;
+
+/// How long a round has taken and how long each player spent on their moves.
+#[derive(Clone, Debug)]
+pub struct Stats {
+    started: Instant,
+    // How long the round took, once it has ended.
+    ended: Option<Duration>,
+    // When the player to move started thinking.
+    thinking: Instant,
+    // Who played each move and how long they took over it, in order of play.
+    moves: Vec<(Player, Duration)>,
+}
+
+impl Default for Stats {
+    fn default() -> Stats {
+        Stats::new()
+    }
+}
+
+impl Stats {
+    pub fn new() -> Stats {
+        let now = Instant::now();
+        Stats {
+            started: now,
+            ended: None,
+            thinking: now,
+            moves: vec![],
+        }
+    }
+
+    /// Start timing a new round.
+    pub fn reset(&mut self) {
+        *self = Stats::new();
+    }
+
+    /// Record that the given player has moved, and start timing the reply.
+    pub fn moved(&mut self, player: Player) {
+        let now = Instant::now();
+        self.moves.push((player, now - self.thinking));
+        self.thinking = now;
+    }
+
+    /// Forget the last move, such as when it is taken back, reopening the
+    /// round if it had ended.
+    pub fn undone(&mut self) {
+        self.moves.pop();
+        self.ended = None;
+        self.thinking = Instant::now();
+    }
+
+    /// Stop the round's time.
+    pub fn finish(&mut self) {
+        if self.ended.is_none() {
+            self.ended = Some(self.started.elapsed());
+        }
+    }
+
+    pub fn moves(&self) -> usize {
+        self.moves.len()
+    }
+
+    /// Time since the round started, or how long it took if it has ended.
+    pub fn elapsed(&self) -> Duration {
+        self.ended.unwrap_or_else(|| self.started.elapsed())
+    }
+
+    /// Average time the player took over each of their moves, or `None` if
+    /// they have not moved.
+    pub fn average_think(&self, player: Player) -> Option<Duration> {
+        let times: Vec<Duration> = self
+            .moves
+            .iter()
+            .filter(|(mover, _)| *mover == player)
+            .map(|(_, time)| *time)
+            .collect();
+        if times.is_empty() {
+            return None;
+        }
+        Some(times.iter().sum::<Duration>() / times.len() as u32)
+    }
+
+    /// One line summary suitable for display.
+    pub fn summary(&self) -> String {
+        let think = |player: Player| {
+            self.average_think(player)
+                .map(clock::format)
+                .unwrap_or_else(|| "-".to_owned())
+        };
+        format!(
+            "{} moves in {}, average think O {}  X {}",
+            self.moves(),
+            clock::format(self.elapsed()),
+            think(Player::Naughts),
+            think(Player::Crosses)
+        )
+    }
+}
@@ -0,0 +1,144 @@
+use ggez::graphics::Color;
+
+use crate::game::{Axis, Game};
+use crate::theme::{self, Shape, Theme};
+
+/// Width and height of a cell in SVG user units.
+const CELL: f32 = theme::REFERENCE_CELL;
+
+/// Render the board as a standalone SVG document.
+///
+/// The document is sized in cells rather than pixels so that it scales to any
+/// resolution. Strokes and pieces are sized as they would be on screen for a
+/// cell of the same width.
+pub fn render(game: &Game, theme: &Theme) -> String {
+    let side = CELL * game.size as f32;
+    let mut svg = String::new();
+    svg.push_str(&format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {0} {0}" width="{0}" height="{0}">"#,
+        side
+    ));
+    svg.push('\n');
+    let background: Color = theme.background.into();
+    if background.a > 0.0 {
+        svg.push_str(&format!(
+            r#"  <rect width="{0}" height="{0}" fill="{1}" fill-opacity="{2}"/>"#,
+            side,
+            rgb(background),
+            background.a
+        ));
+        svg.push('\n');
+    }
+    let grid = theme.grid_color(game.turn);
+    let grid_stroke = theme::stroke(theme.grid_stroke, CELL);
+    for ii in 1..game.size {
+        let offset = CELL * ii as f32;
+        line(&mut svg, [offset, 0.0], [offset, side], grid_stroke, grid);
+        line(&mut svg, [0.0, offset], [side, offset], grid_stroke, grid);
+    }
+    let size = theme::mark_radius(CELL);
+    let piece_stroke = theme::stroke(theme.piece_stroke, CELL);
+    for ((ii, jj), player) in game.grid.pieces() {
+        let [x, y] = center(ii, jj);
+        let color = theme.player_color(player);
+        match theme.shape(player) {
+            Shape::Circle => {
+                svg.push_str(&format!(
+                    r#"  <circle cx="{}" cy="{}" r="{}" fill="none" stroke="{}" stroke-opacity="{}" stroke-width="{}"/>"#,
+                    x,
+                    y,
+                    size,
+                    rgb(color),
+                    color.a,
+                    piece_stroke
+                ));
+                svg.push('\n');
+            }
+            Shape::Cross => {
+                line(
+                    &mut svg,
+                    [x - size, y - size],
+                    [x + size, y + size],
+                    piece_stroke,
+                    color,
+                );
+                line(
+                    &mut svg,
+                    [x + size, y - size],
+                    [x - size, y + size],
+                    piece_stroke,
+                    color,
+                );
+            }
+            Shape::Triangle => {
+                let points: Vec<String> = theme::triangle(x, y, size)
+                    .iter()
+                    .map(|[x, y]| format!("{},{}", x, y))
+                    .collect();
+                svg.push_str(&format!(
+                    r#"  <polygon points="{}" fill="none" stroke="{}" stroke-opacity="{}" stroke-width="{}" stroke-linejoin="round"/>"#,
+                    points.join(" "),
+                    rgb(color),
+                    color.a,
+                    piece_stroke
+                ));
+                svg.push('\n');
+            }
+            Shape::Square => {
+                let half = theme::square_half_side(size);
+                svg.push_str(&format!(
+                    r#"  <rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="{}" stroke-opacity="{}" stroke-width="{}"/>"#,
+                    x - half,
+                    y - half,
+                    half * 2.0,
+                    half * 2.0,
+                    rgb(color),
+                    color.a,
+                    piece_stroke
+                ));
+                svg.push('\n');
+            }
+        }
+    }
+    if let Some((_, Axis(start, end))) = &game.winner {
+        line(
+            &mut svg,
+            center(start.0, start.1),
+            center(end.0, end.1),
+            theme::stroke(theme.win_line_stroke, CELL),
+            theme.win_line.into(),
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn center(col: usize, row: usize) -> [f32; 2] {
+    [
+        CELL * col as f32 + CELL / 2.0,
+        CELL * row as f32 + CELL / 2.0,
+    ]
+}
+
+fn line(svg: &mut String, from: [f32; 2], to: [f32; 2], stroke: f32, color: Color) {
+    svg.push_str(&format!(
+        r#"  <line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-opacity="{}" stroke-width="{}" stroke-linecap="round"/>"#,
+        from[0],
+        from[1],
+        to[0],
+        to[1],
+        rgb(color),
+        color.a,
+        stroke
+    ));
+    svg.push('\n');
+}
+
+fn rgb(color: Color) -> String {
+    format!(
+        "rgb({}, {}, {})",
+        (color.r * 255.0).round() as u8,
+        (color.g * 255.0).round() as u8,
+        (color.b * 255.0).round() as u8
+    )
+}
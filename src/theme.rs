@@ -0,0 +1,310 @@
+use ggez::filesystem;
+use ggez::graphics;
+use ggez::Context;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::Path;
+
+use crate::game::Player;
+
+/// Name of the theme file looked up in the user config directory when no
+/// explicit path is given.
+const THEME_FILE: &str = "/theme.toml";
+
+/// Minimum difference in relative luminance between a piece and the
+/// background before the piece color is adjusted for contrast.
+const MIN_CONTRAST: f32 = 0.5;
+
+/// Width of the cell, in pixels, that the stroke widths of a theme are given
+/// for. Strokes are scaled in proportion to the cells they are drawn in.
+pub const REFERENCE_CELL: f32 = 100.0;
+
+/// Thinnest and thickest a stroke is drawn, in pixels, whatever the cell size.
+const MIN_STROKE: f32 = 1.0;
+const MAX_STROKE: f32 = 16.0;
+
+/// Smallest radius, in pixels, a piece is drawn with.
+const MIN_MARK: f32 = 2.0;
+
+/// Whether the board is drawn on a dark or a light background.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub enum Mode {
+    Dark,
+    Light,
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Mode::Dark
+    }
+}
+
+impl Mode {
+    pub fn toggle(self) -> Mode {
+        match self {
+            Mode::Dark => Mode::Light,
+            Mode::Light => Mode::Dark,
+        }
+    }
+}
+
+/// Piece colors chosen to stay distinguishable with common color vision
+/// deficiencies. Selecting a palette replaces the theme's piece colors.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Palette {
+    /// Orange and sky blue from the Okabe-Ito palette, safe for red-green
+    /// color blindness.
+    OkabeIto,
+    /// Yellow and magenta at full saturation.
+    HighContrast,
+    /// Red and teal, safe for blue-yellow color blindness.
+    Tritan,
+}
+
+impl Palette {
+    /// Names accepted by `from_name`, as written in the theme file.
+    pub const NAMES: &'static [&'static str] = &["okabe-ito", "high-contrast", "tritan"];
+
+    pub fn from_name(name: &str) -> Option<Palette> {
+        match name {
+            "okabe-ito" => Some(Palette::OkabeIto),
+            "high-contrast" => Some(Palette::HighContrast),
+            "tritan" => Some(Palette::Tritan),
+            _ => None,
+        }
+    }
+
+    /// The naughts and crosses colors of the palette.
+    pub fn colors(self) -> ([f32; 4], [f32; 4]) {
+        match self {
+            Palette::OkabeIto => ([0.902, 0.624, 0.0, 1.0], [0.337, 0.706, 0.914, 1.0]),
+            Palette::HighContrast => ([1.0, 1.0, 0.0, 1.0], [1.0, 0.0, 1.0, 1.0]),
+            Palette::Tritan => ([0.835, 0.196, 0.196, 1.0], [0.0, 0.6, 0.6, 1.0]),
+        }
+    }
+}
+
+/// How the players' pieces are drawn.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Marks {
+    /// Naughts and crosses.
+    Classic,
+    /// Triangles for naughts and squares for crosses, which tell the players
+    /// apart without relying on color or on the fine detail of the marks.
+    Shapes,
+}
+
+impl Default for Marks {
+    fn default() -> Self {
+        Marks::Classic
+    }
+}
+
+/// The outline a piece is drawn with.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Shape {
+    Circle,
+    Cross,
+    Triangle,
+    Square,
+}
+
+/// Images the pieces are drawn with instead of vector marks.
+///
+/// Paths are PNG files within the resource directories, such as
+/// `/skins/wood/naughts.png`. Images are scaled to the size of the marks they
+/// replace and drawn in their own colors.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Skin {
+    pub naughts: String,
+    pub crosses: String,
+}
+
+/// Colors and stroke widths used to render the board.
+///
+/// Colors are RGBA quadruples in the range 0.0 to 1.0. Stroke widths are
+/// given for a cell `REFERENCE_CELL` pixels wide and scale with the cells
+/// actually drawn. Any field left out of
+/// the config file falls back to the default theme. The file describes the
+/// dark mode palette; the light mode palette is derived from it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Theme {
+    pub background: [f32; 4],
+    /// Color of the grid lines. When unset the grid takes the color of the
+    /// player whose turn it is.
+    pub grid: Option<[f32; 4]>,
+    pub naughts: [f32; 4],
+    pub crosses: [f32; 4],
+    pub win_line: [f32; 4],
+    /// Color of overlaid text such as the score.
+    pub text: [f32; 4],
+    pub grid_stroke: f32,
+    pub piece_stroke: f32,
+    pub win_line_stroke: f32,
+    /// Accessible palette overriding the piece colors, if any.
+    pub palette: Option<Palette>,
+    pub marks: Marks,
+    pub skin: Option<Skin>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            background: [0.0, 0.0, 0.0, 0.0],
+            grid: None,
+            naughts: [1.0, 0.647, 0.0, 1.0],
+            crosses: [0.0, 0.35, 1.0, 1.0],
+            win_line: [1.0, 1.0, 1.0, 1.0],
+            text: [1.0, 1.0, 1.0, 1.0],
+            grid_stroke: 2.0,
+            piece_stroke: 2.0,
+            win_line_stroke: 2.0,
+            palette: None,
+            marks: Marks::default(),
+            skin: None,
+        }
+    }
+}
+
+impl Theme {
+    /// Load the theme from `path` if given, otherwise from the user config
+    /// directory, falling back to the default theme if no file exists there.
+    pub fn load(ctx: &mut Context, path: Option<&Path>) -> ggez::GameResult<Theme> {
+        let contents = match path {
+            Some(path) => std::fs::read_to_string(path)?,
+            None => {
+                if !filesystem::exists(ctx, THEME_FILE) {
+                    return Ok(Theme::default());
+                }
+                let mut contents = String::new();
+                filesystem::open(ctx, THEME_FILE)?.read_to_string(&mut contents)?;
+                contents
+            }
+        };
+        let mut theme: Theme = toml::from_str(&contents)
+            .map_err(|err| ggez::GameError::ConfigError(format!("parsing theme: {}", err)))?;
+        if let Some(palette) = theme.palette {
+            theme.set_palette(palette);
+        }
+        Ok(theme)
+    }
+
+    /// Replace the piece colors with those of the palette.
+    pub fn set_palette(&mut self, palette: Palette) {
+        let (naughts, crosses) = palette.colors();
+        self.palette = Some(palette);
+        self.naughts = naughts;
+        self.crosses = crosses;
+    }
+
+    /// Derive the palette for the given mode.
+    pub fn for_mode(&self, mode: Mode) -> Theme {
+        match mode {
+            Mode::Dark => self.clone(),
+            Mode::Light => {
+                let background = invert(self.background);
+                Theme {
+                    background: background,
+                    grid: self.grid.map(|color| contrast(color, background)),
+                    naughts: contrast(self.naughts, background),
+                    crosses: contrast(self.crosses, background),
+                    win_line: invert(self.win_line),
+                    text: invert(self.text),
+                    ..self.clone()
+                }
+            }
+        }
+    }
+
+    pub fn player_color(&self, player: Player) -> graphics::Color {
+        match player {
+            Player::Naughts => self.naughts.into(),
+            Player::Crosses => self.crosses.into(),
+        }
+    }
+
+    pub fn shape(&self, player: Player) -> Shape {
+        match (self.marks, player) {
+            (Marks::Classic, Player::Naughts) => Shape::Circle,
+            (Marks::Classic, Player::Crosses) => Shape::Cross,
+            (Marks::Shapes, Player::Naughts) => Shape::Triangle,
+            (Marks::Shapes, Player::Crosses) => Shape::Square,
+        }
+    }
+
+    pub fn grid_color(&self, turn: Player) -> graphics::Color {
+        match self.grid {
+            Some(color) => color.into(),
+            None => self.player_color(turn),
+        }
+    }
+}
+
+/// Scale a stroke width from the theme to a cell `cell` pixels wide, so lines
+/// are neither hairline on large windows nor chunky on small ones.
+pub fn stroke(width: f32, cell: f32) -> f32 {
+    (width * cell / REFERENCE_CELL)
+        .min(MAX_STROKE)
+        .max(MIN_STROKE)
+}
+
+/// Corners of a triangle pointing up, centered on `(x, y)` and fitting in a
+/// circle of the given radius.
+pub fn triangle(x: f32, y: f32, radius: f32) -> [[f32; 2]; 3] {
+    let (dx, dy) = (radius * 0.866, radius * 0.5);
+    [[x, y - radius], [x + dx, y + dy], [x - dx, y + dy]]
+}
+
+/// Half the side of a square piece fitting in a circle of the given radius.
+pub fn square_half_side(radius: f32) -> f32 {
+    radius * 0.8
+}
+
+/// Radius of a piece drawn in a cell `cell` pixels wide. Pieces keep a visible
+/// size in tiny cells but never spill out of the cell.
+pub fn mark_radius(cell: f32) -> f32 {
+    (cell / 4.0).max(MIN_MARK).min(cell / 2.0)
+}
+
+fn luminance(color: [f32; 4]) -> f32 {
+    0.2126 * color[0] + 0.7152 * color[1] + 0.0722 * color[2]
+}
+
+fn invert(color: [f32; 4]) -> [f32; 4] {
+    [1.0 - color[0], 1.0 - color[1], 1.0 - color[2], 1.0]
+}
+
+// Scales the color towards black or white, whichever is further from the
+// background, until the luminance difference reaches `MIN_CONTRAST`.
+fn contrast(color: [f32; 4], background: [f32; 4]) -> [f32; 4] {
+    let (fg, bg) = (luminance(color), luminance(background));
+    if (fg - bg).abs() >= MIN_CONTRAST {
+        return color;
+    }
+    if bg > 0.5 {
+        let target = (bg - MIN_CONTRAST).max(0.0);
+        let scale = if fg > 0.0 { target / fg } else { 0.0 };
+        [
+            color[0] * scale,
+            color[1] * scale,
+            color[2] * scale,
+            color[3],
+        ]
+    } else {
+        let target = (bg + MIN_CONTRAST).min(1.0);
+        let mix = if fg < 1.0 {
+            (target - fg) / (1.0 - fg)
+        } else {
+            0.0
+        };
+        [
+            color[0] + (1.0 - color[0]) * mix,
+            color[1] + (1.0 - color[1]) * mix,
+            color[2] + (1.0 - color[2]) * mix,
+            color[3],
+        ]
+    }
+}
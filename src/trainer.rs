@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+
+use crate::ai;
+use crate::game::Game;
+use crate::rng::Rng;
+
+/// Openings the trainer quizzes on.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Opening {
+    /// The classic 3x3 game.
+    Classic,
+    /// Four in a row on a 7x7 board with gravity.
+    Gravity,
+}
+
+impl Opening {
+    pub fn name(self) -> &'static str {
+        match self {
+            Opening::Classic => "3x3",
+            Opening::Gravity => "gravity",
+        }
+    }
+
+    fn start(self) -> Game {
+        match self {
+            Opening::Classic => Game::new(3, 3, false),
+            Opening::Gravity => Game::new(7, 4, true),
+        }
+    }
+
+    // Fewest and most moves played before a question is asked.
+    fn plies(self) -> (usize, usize) {
+        match self {
+            Opening::Classic => (1, 3),
+            Opening::Gravity => (2, 6),
+        }
+    }
+
+    // How deep the search looks to judge answers. The classic board is solved
+    // outright.
+    fn depth(self, game: &Game) -> usize {
+        match self {
+            Opening::Classic => game.size * game.size - game.history.len(),
+            Opening::Gravity => 5,
+        }
+    }
+}
+
+/// How often the best response was found.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct Accuracy {
+    pub asked: usize,
+    pub correct: usize,
+}
+
+impl Accuracy {
+    pub fn record(&mut self, correct: bool) {
+        self.asked += 1;
+        if correct {
+            self.correct += 1;
+        }
+    }
+
+    /// One line summary suitable for display.
+    pub fn summary(&self) -> String {
+        if self.asked == 0 {
+            return "no answers yet".to_owned();
+        }
+        format!(
+            "{} of {} ({}%)",
+            self.correct,
+            self.asked,
+            self.correct * 100 / self.asked
+        )
+    }
+}
+
+/// Accuracy in each kind of opening, kept in the settings so it can be
+/// followed over time.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Progress {
+    pub classic: Accuracy,
+    pub gravity: Accuracy,
+}
+
+impl Progress {
+    pub fn accuracy(&mut self, opening: Opening) -> &mut Accuracy {
+        match opening {
+            Opening::Classic => &mut self.classic,
+            Opening::Gravity => &mut self.gravity,
+        }
+    }
+}
+
+/// Quizzes the player on the best response to openings, reached by a few
+/// strong moves with some variety in which of the strong moves is played.
+#[derive(Clone, Debug)]
+pub struct Trainer {
+    pub opening: Opening,
+    /// The position the player is to find the best move in.
+    pub question: Game,
+    /// The best moves in the question, as found by the search.
+    pub best: Vec<(usize, usize)>,
+    /// Whether the question has been answered, and if so correctly.
+    pub answered: Option<bool>,
+    rng: Rng,
+}
+
+impl Trainer {
+    pub fn new(opening: Opening, seed: u64) -> Trainer {
+        let mut trainer = Trainer {
+            opening: opening,
+            question: opening.start(),
+            best: vec![],
+            answered: None,
+            rng: Rng::new(seed),
+        };
+        trainer.next();
+        trainer
+    }
+
+    /// Move on to a new question.
+    pub fn next(&mut self) {
+        let (fewest, most) = self.opening.plies();
+        let plies = fewest + self.rng.below(most - fewest + 1);
+        let mut game = self.opening.start();
+        let mut best = self.best_moves(&game);
+        while game.history.len() < plies && game.in_progress() {
+            let (col, row) = best[self.rng.below(best.len())];
+            game.place(col, row);
+            best = self.best_moves(&game);
+        }
+        self.question = game;
+        self.best = best;
+        self.answered = None;
+    }
+
+    /// Judge the player's answer, returning whether it was one of the best
+    /// moves, or `None` if the question was already answered.
+    pub fn answer(&mut self, cell: (usize, usize)) -> Option<bool> {
+        if self.answered.is_some() {
+            return None;
+        }
+        let correct = self.best.contains(&cell);
+        self.answered = Some(correct);
+        Some(correct)
+    }
+
+    // Moves scoring as well as the best, or any winning move once a win is
+    // forced.
+    fn best_moves(&self, game: &Game) -> Vec<(usize, usize)> {
+        let ranked = ai::rank(game, self.opening.depth(game));
+        let top = match ranked.first() {
+            Some((_, score)) => *score,
+            None => return vec![],
+        };
+        ranked
+            .into_iter()
+            .filter(|(_, score)| *score == top || (top >= ai::WIN && *score >= ai::WIN))
+            .map(|(cell, _)| cell)
+            .collect()
+    }
+}
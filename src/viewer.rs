@@ -0,0 +1,121 @@
+use std::time::{Duration, Instant};
+
+use crate::game::Game;
+use crate::replay::Replay;
+use crate::review::Review;
+
+/// Steps through the moves of a replay, either by hand or automatically.
+pub struct Viewer {
+    pub replay: Replay,
+    /// The position after `position` moves of the replay.
+    pub game: Game,
+    pub position: usize,
+    pub autoplay: bool,
+    /// Time between moves while autoplaying.
+    pub delay: Duration,
+    /// The computer's review of the replay, if it has been reviewed.
+    pub review: Option<Review>,
+    last_step: Instant,
+}
+
+impl Viewer {
+    pub fn new(replay: Replay, delay: Duration) -> Viewer {
+        Viewer {
+            game: replay.start(),
+            replay: replay,
+            position: 0,
+            autoplay: false,
+            delay: delay,
+            review: None,
+            last_step: Instant::now(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.replay.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.replay.moves.is_empty()
+    }
+
+    /// Step one move forward, returning whether there was a move to step to.
+    pub fn forward(&mut self) -> bool {
+        match self.replay.moves.get(self.position) {
+            Some((col, row)) => {
+                self.game.place(*col, *row);
+                self.position += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Step one move back, returning whether there was a move to step back.
+    pub fn back(&mut self) -> bool {
+        if self.position == 0 {
+            return false;
+        }
+        self.game.undo();
+        self.position -= 1;
+        true
+    }
+
+    pub fn seek(&mut self, position: usize) {
+        self.position = position.min(self.len());
+        self.game = self.replay.position(self.position);
+    }
+
+    /// Step to just after the next move the review found a mistake in,
+    /// returning whether there was one.
+    pub fn next_mistake(&mut self) -> bool {
+        let next = match &self.review {
+            Some(review) => review
+                .notes
+                .iter()
+                .enumerate()
+                .skip(self.position)
+                .find(|(_, note)| note.mistake.is_some())
+                .map(|(ii, _)| ii + 1),
+            None => None,
+        };
+        match next {
+            Some(position) => {
+                self.seek(position);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The review's comment on the move just stepped to, if the replay has
+    /// been reviewed.
+    pub fn comment(&self) -> Option<String> {
+        let index = self.position.checked_sub(1)?;
+        self.review.as_ref()?.comment(index)
+    }
+
+    pub fn toggle_autoplay(&mut self) {
+        // Starting autoplay from the end replays the round from the start.
+        if !self.autoplay && self.position == self.len() {
+            self.seek(0);
+        }
+        self.autoplay = !self.autoplay;
+        self.last_step = Instant::now();
+    }
+
+    /// Advance the autoplay if the delay has passed, returning whether the
+    /// position changed.
+    pub fn tick(&mut self) -> bool {
+        if !self.autoplay || self.last_step.elapsed() < self.delay {
+            return false;
+        }
+        self.last_step = Instant::now();
+        if self.forward() {
+            true
+        } else {
+            self.autoplay = false;
+            false
+        }
+    }
+}
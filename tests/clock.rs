@@ -0,0 +1,44 @@
+//! Checks taking moves back restores the clock and running it again changes
+//! nothing, so neither can bank extra time.
+
+use std::thread;
+use std::time::Duration;
+
+use ticktacktoe::clock::{Clock, TimeControl};
+use ticktacktoe::game::Player;
+
+#[test]
+fn undone_moves_give_back_their_increment() {
+    let mut clock = Clock::new(TimeControl::parse("60+2").expect("parsing time control"));
+    let limit = Duration::from_secs(60);
+    for _ in 0..3 {
+        clock.run(Player::Naughts);
+        clock.moved(Player::Naughts);
+        clock.run(Player::Crosses);
+        clock.undone();
+    }
+    assert_eq!(clock.remaining(Player::Naughts), limit);
+    assert_eq!(clock.running(), None);
+
+    clock.run(Player::Naughts);
+    clock.moved(Player::Naughts);
+    clock.run(Player::Crosses);
+    clock.moved(Player::Crosses);
+    clock.undone();
+    assert!(clock.remaining(Player::Naughts) > limit);
+    assert_eq!(clock.remaining(Player::Crosses), limit);
+}
+
+#[test]
+fn running_the_same_player_again_keeps_their_delay_used() {
+    let mut clock = Clock::new(TimeControl {
+        delay: Duration::from_millis(30),
+        ..TimeControl::parse("60").expect("parsing time control")
+    });
+    clock.run(Player::Naughts);
+    thread::sleep(Duration::from_millis(20));
+    clock.run(Player::Naughts);
+    thread::sleep(Duration::from_millis(20));
+    assert_eq!(clock.running(), Some(Player::Naughts));
+    assert!(clock.remaining(Player::Naughts) < Duration::from_secs(60));
+}
@@ -0,0 +1,67 @@
+//! Checks a scripted game played without a window, as other programs drive
+//! it through stdin and stdout.
+
+use std::io::Cursor;
+
+use ticktacktoe::game::Game;
+use ticktacktoe::headless;
+
+#[test]
+fn scripted_round_is_played_to_a_win() {
+    let moves = "# Naughts take the a file.\na3\na3\nb2\nz9\na2\nb3\na1\nc3\n";
+    let names = ("Ann".to_owned(), "Bob".to_owned());
+    let mut output = vec![];
+    headless::run(
+        Cursor::new(moves),
+        &mut output,
+        Game::new(3, 3, false),
+        &names,
+    )
+    .expect("playing the round");
+    // Moves after the win are not read.
+    let expected = "  a b c
+3 . . .
+2 . . .
+1 . . .
+
+Ann to play.
+Ann plays a3.
+  a b c
+3 O . .
+2 . . .
+1 . . .
+
+Bob to play.
+Illegal move \"a3\". Bob to play.
+Bob plays b2.
+  a b c
+3 O . .
+2 . X .
+1 . . .
+
+Ann to play.
+Illegal move \"z9\". Ann to play.
+Ann plays a2.
+  a b c
+3 O . .
+2 O X .
+1 . . .
+
+Bob to play.
+Bob plays b3.
+  a b c
+3 O X .
+2 O X .
+1 . . .
+
+Ann to play.
+Ann plays a1.
+  a b c
+3 O X .
+2 O X .
+1 O . .
+
+Ann wins.
+";
+    assert_eq!(String::from_utf8(output).expect("utf-8 output"), expected);
+}
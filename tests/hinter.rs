@@ -0,0 +1,28 @@
+//! Checks hints are found in the background and belong to the position they
+//! were asked for.
+
+use std::thread;
+use std::time::Duration;
+
+use ticktacktoe::analysis::Hinter;
+use ticktacktoe::game::Game;
+
+#[test]
+fn hints_arrive_for_their_position() {
+    let mut game = Game::new(3, 3, false);
+    game.place(0, 0);
+    game.place(1, 1);
+    game.place(1, 0);
+    let hinter = Hinter::new(&game);
+    let hint = loop {
+        if let Some(hint) = hinter.poll() {
+            break hint;
+        }
+        thread::sleep(Duration::from_millis(1));
+    };
+    // Crosses must block the top row.
+    assert_eq!(hint, Some((2, 0)));
+    assert!(hinter.is_for(&game));
+    game.place(2, 0);
+    assert!(!hinter.is_for(&game));
+}
@@ -0,0 +1,69 @@
+//! Checks key names are read as documented and that every key the game
+//! responds to goes through the conflict check.
+
+use ggez::event::KeyCode;
+use ggez::input::keyboard::KeyMods;
+
+use ticktacktoe::keymap::{Command, KeyBindings, Keymap};
+
+#[test]
+fn keys_are_parsed_with_their_modifiers() {
+    let mut keys = KeyBindings {
+        undo: vec!["ctrl + shift + z".to_owned(), "BackSpace".to_owned()],
+        ..KeyBindings::default()
+    };
+    let keymap = Keymap::new(&keys).expect("building keymap");
+    let both = KeyMods::CTRL | KeyMods::SHIFT;
+    assert_eq!(keymap.command(KeyCode::Z, both), Some(Command::Undo));
+    assert_eq!(
+        keymap.command(KeyCode::Back, KeyMods::NONE),
+        Some(Command::Undo)
+    );
+    // Modifiers must match exactly.
+    assert_eq!(keymap.command(KeyCode::Z, KeyMods::CTRL), None);
+    assert_eq!(keymap.command(KeyCode::U, KeyMods::NONE), None);
+
+    keys.undo = vec!["Hyper+U".to_owned()];
+    assert!(Keymap::new(&keys).is_err());
+    keys.undo = vec!["Banana".to_owned()];
+    assert!(Keymap::new(&keys).is_err());
+}
+
+#[test]
+fn the_default_keys_do_not_conflict() {
+    let keymap = Keymap::default();
+    assert_eq!(
+        keymap.command(KeyCode::Space, KeyMods::NONE),
+        Some(Command::Place)
+    );
+    assert_eq!(
+        keymap.command(KeyCode::P, KeyMods::NONE),
+        Some(Command::Autoplay)
+    );
+    assert_eq!(
+        keymap.command(KeyCode::Left, KeyMods::NONE),
+        Some(Command::Left)
+    );
+}
+
+#[test]
+fn navigation_keys_are_checked_for_conflicts() {
+    let mut keys = KeyBindings {
+        hint: vec!["Left".to_owned()],
+        ..KeyBindings::default()
+    };
+    let err = Keymap::new(&keys).expect_err("Left is bound twice");
+    assert!(err.contains("hint") && err.contains("left"), "{}", err);
+
+    // Rebinding a navigation key frees its default for other commands.
+    keys.left = vec!["J".to_owned()];
+    let keymap = Keymap::new(&keys).expect("building keymap");
+    assert_eq!(
+        keymap.command(KeyCode::Left, KeyMods::NONE),
+        Some(Command::Hint)
+    );
+    assert_eq!(
+        keymap.command(KeyCode::J, KeyMods::NONE),
+        Some(Command::Left)
+    );
+}
@@ -0,0 +1,42 @@
+//! Checks the settings survive being saved and loaded, whichever of them are
+//! set.
+
+use std::path::PathBuf;
+
+use ticktacktoe::settings::{FirstMove, Settings};
+use ticktacktoe::theme::Mode;
+use ticktacktoe::trainer::Opening;
+
+#[test]
+fn settings_survive_a_round_trip_with_everything_set() {
+    let mut settings = Settings {
+        mode: Mode::Light,
+        confirm_moves: true,
+        coordinates: true,
+        first_move: FirstMove::Naughts,
+        size: Some(7),
+        win: Some(4),
+        gravity: Some(true),
+        next_round: Some(5),
+        theme: Some(PathBuf::from("/themes/dusk.toml")),
+        window_size: Some((800.0, 600.0)),
+        window_position: Some((10.0, 20.0)),
+        ..Settings::default()
+    };
+    settings.keys.undo = vec!["Ctrl+U".to_owned()];
+    settings.trainer.accuracy(Opening::Gravity).record(true);
+
+    let text = settings.to_toml().expect("encoding settings");
+    let loaded = Settings::parse(&text).expect("parsing settings");
+    assert_eq!(loaded.to_toml(), Ok(text));
+    assert_eq!(loaded.mode, Mode::Light);
+    assert_eq!(loaded.window_position, Some((10.0, 20.0)));
+    assert_eq!(loaded.keys.undo, vec!["Ctrl+U".to_owned()]);
+    assert_eq!(loaded.trainer.gravity.correct, 1);
+}
+
+#[test]
+fn malformed_settings_are_reported() {
+    assert!(Settings::parse("size = \"seven\"").is_err());
+    assert!(Settings::parse("").is_ok());
+}